@@ -0,0 +1,22 @@
+// Controls are generated from the comments, see "Parameters" in the README
+struct Params {
+    center: vec2<f32>, // @range(-0.5, 0.5)
+    frequency: f32,    // @range(1, 80) @default(40)
+    speed: f32,        // @range(0, 10) @default(4)
+    petals: i32,       // @range(0, 12) @default(5)
+    animate: u32,      // @checkbox @default(1)
+    tint: vec3<f32>,   // @color @default(1, 0.6, 0.2)
+}
+
+@group(0) @binding(1)
+var<uniform> params: Params;
+
+@fragment
+fn fs_main(in: FragmentInput) -> @location(0) vec4<f32> {
+    let aspect = globals.resolution.x / globals.resolution.y;
+    let p = (in.uv - 0.5 - params.center * vec2(1.0, -1.0)) * vec2(aspect, 1.0);
+    let d = length(p) + 0.02 * sin(atan2(p.y, p.x) * f32(params.petals));
+    let time = globals.time * f32(params.animate);
+    let g = 0.5 + 0.5 * sin(d * params.frequency - time * params.speed);
+    return vec4<f32>(params.tint * g, 1.0);
+}
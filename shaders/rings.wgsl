@@ -0,0 +1,8 @@
+@fragment
+fn fs_main(in: FragmentInput) -> @location(0) vec4<f32> {
+    let aspect = globals.resolution.x / globals.resolution.y;
+    let p = (in.uv - 0.5) * vec2(aspect, 1.0);
+    let d = length(p);
+    let g = 0.5 + 0.5 * sin(d * 40.0 - globals.time * 4.0);
+    return vec4<f32>(in.uv.x * g, g, in.uv.y * g, 1.0);
+}
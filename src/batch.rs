@@ -0,0 +1,151 @@
+use std::path::{Path, PathBuf};
+
+use serde::Serialize;
+
+use crate::cli::{OutputFormat, ValidateArgs};
+use crate::diagnostic::{Diagnostic, Severity};
+use crate::language::{self, Shader};
+use crate::validator;
+
+/// Validation result of a single file, printed as JSON
+#[derive(Debug, Serialize)]
+struct Report {
+    file: PathBuf,
+    valid: bool,
+    diagnostics: Vec<Entry>,
+}
+
+/// [`Diagnostic`] with the location of every label, lines and columns are 1-based
+#[derive(Debug, Serialize)]
+struct Entry {
+    severity: &'static str,
+    message: String,
+    line: Option<usize>,
+    column: Option<usize>,
+    labels: Vec<LabelEntry>,
+    notes: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct LabelEntry {
+    line: usize,
+    column: usize,
+    end_line: usize,
+    end_column: usize,
+    message: String,
+}
+
+impl Entry {
+    fn new(diagnostic: &Diagnostic) -> Self {
+        let location = diagnostic.location();
+        Entry {
+            severity: match diagnostic.severity {
+                Severity::Error => "error",
+            },
+            message: diagnostic.message.clone(),
+            line: location.map(|location| location.line),
+            column: location.map(|location| location.column),
+            labels: diagnostic
+                .labels
+                .iter()
+                .map(|label| LabelEntry {
+                    line: label.start.line,
+                    column: label.start.column,
+                    end_line: label.end.line,
+                    end_column: label.end.column,
+                    message: label.message.clone(),
+                })
+                .collect(),
+            notes: diagnostic.notes.clone(),
+        }
+    }
+
+    /// `file:line:col: severity: message`, the same as compilers print it
+    fn print(&self, file: &Path) {
+        let location = match (self.line, self.column) {
+            (Some(line), Some(column)) => format!(":{line}:{column}"),
+            _ => String::new(),
+        };
+        println!(
+            "{}{location}: {}: {}",
+            file.display(),
+            self.severity,
+            self.message
+        );
+        for label in self.labels.iter().filter(|label| !label.message.is_empty()) {
+            println!(
+                "  {}:{}:{}: {}",
+                file.display(),
+                label.line,
+                label.column,
+                label.message
+            );
+        }
+        for note in &self.notes {
+            println!("  = note: {note}");
+        }
+    }
+}
+
+/// Validates every shader of the `validate` subcommand,
+/// returns `false` if any of them has errors
+pub fn run(args: &ValidateArgs) -> Result<bool, String> {
+    let mut files = Vec::new();
+    for path in &args.paths {
+        if path.is_dir() {
+            collect_shaders(path, &mut files)?;
+        } else {
+            files.push(path.clone());
+        }
+    }
+
+    let reports: Vec<_> = files.into_iter().map(validate_file).collect();
+    let failed = reports.iter().filter(|report| !report.valid).count();
+
+    match args.format {
+        OutputFormat::Human => {
+            for report in &reports {
+                for entry in &report.diagnostics {
+                    entry.print(&report.file);
+                }
+            }
+            println!("Validated {} shaders, {failed} with errors", reports.len());
+        }
+        OutputFormat::Json => {
+            let json = serde_json::to_string_pretty(&reports).map_err(|e| e.to_string())?;
+            println!("{json}");
+        }
+    }
+    Ok(failed == 0)
+}
+
+fn validate_file(file: PathBuf) -> Report {
+    let result = Shader::read(&file)
+        .map_err(Diagnostic::error)
+        .and_then(|shader| validator::validate(&shader));
+    let diagnostics = match &result {
+        Ok(_) => Vec::new(),
+        Err(diagnostic) => vec![Entry::new(diagnostic)],
+    };
+    Report {
+        file,
+        valid: result.is_ok(),
+        diagnostics,
+    }
+}
+
+/// Finds the shader files in `directory` and its subdirectories, sorted by path
+pub fn collect_shaders(directory: &Path, files: &mut Vec<PathBuf>) -> Result<(), String> {
+    let read_dir = std::fs::read_dir(directory)
+        .map_err(|e| format!("Error reading {}: {e}", directory.display()))?;
+    let mut paths: Vec<_> = read_dir.flatten().map(|entry| entry.path()).collect();
+    paths.sort();
+    for path in paths {
+        if path.is_dir() {
+            collect_shaders(&path, files)?;
+        } else if language::is_shader(&path) {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,221 @@
+use std::path::PathBuf;
+
+use clap::{Parser, Subcommand, ValueEnum};
+use iced_wgpu::wgpu;
+use iced_winit::core;
+
+use crate::{DEFAULT_HEIGHT, DEFAULT_WIDTH, SHADER_FILE, highlighter};
+
+/// A minimal WebGPU application which can run your WGSL, GLSL and SPIR-V shaders
+#[derive(Debug, Parser)]
+#[command(version, about)]
+pub struct Args {
+    /// Shader file to open
+    #[arg(default_value = SHADER_FILE)]
+    pub shader: PathBuf,
+
+    /// Initial window width in physical pixels
+    #[arg(long)]
+    pub width: Option<u32>,
+
+    /// Initial window height in physical pixels
+    #[arg(long)]
+    pub height: Option<u32>,
+
+    /// Start with the text editor hidden
+    #[arg(long)]
+    pub hide_editor: bool,
+
+    /// Color theme of the UI and the syntax highlight
+    #[arg(long, value_enum, default_value_t = Theme::SolarizedDark)]
+    pub theme: Theme,
+
+    /// Presentation mode of the window surface
+    #[arg(long, value_enum, default_value_t = PresentMode::AutoVsync)]
+    pub present_mode: PresentMode,
+
+    /// Don't reload the shader when the file changes on disk
+    #[arg(long)]
+    pub no_watch: bool,
+
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Render a frame to a PNG without opening a window,
+    /// exits with a non-zero code on shader errors
+    Render(RenderArgs),
+    /// Validate shader files and directories without opening a window,
+    /// exits with a non-zero code if any shader has errors
+    Validate(ValidateArgs),
+    /// Render every shader of a directory and compare it with the reference images,
+    /// exits with a non-zero code if any of them differs
+    Golden(GoldenArgs),
+    /// Convert a Shadertoy snippet or JSON export to GLSL fragment shaders, a file per pass
+    Import(ImportArgs),
+}
+
+#[derive(Debug, clap::Args)]
+pub struct RenderArgs {
+    /// Shader file to render
+    pub shader: PathBuf,
+
+    /// PNG file to write
+    #[arg(short, long)]
+    pub output: PathBuf,
+
+    /// Image width in pixels
+    #[arg(long, default_value_t = DEFAULT_WIDTH)]
+    pub width: u32,
+
+    /// Image height in pixels
+    #[arg(long, default_value_t = DEFAULT_HEIGHT)]
+    pub height: u32,
+
+    /// Value of `globals.time` in seconds
+    #[arg(long, default_value_t = 0.0)]
+    pub time: f32,
+
+    /// Value of `globals.frame`
+    #[arg(long, default_value_t = 0)]
+    pub frame: u32,
+
+    /// Vertex entry point, `vs_main` or the first one by default
+    #[arg(long)]
+    pub vertex: Option<String>,
+
+    /// Fragment entry point, `fs_main` or the first one by default
+    #[arg(long)]
+    pub fragment: Option<String>,
+
+    /// Run the shader with the CPU interpreter instead of the GPU
+    #[arg(long)]
+    pub cpu: bool,
+}
+
+#[derive(Debug, clap::Args)]
+pub struct ValidateArgs {
+    /// Shader files or directories searched for `.wgsl`, `.vert`, `.frag`, `.comp` and `.spv` files
+    #[arg(default_value = "shaders")]
+    pub paths: Vec<PathBuf>,
+
+    /// Format of the printed diagnostics
+    #[arg(long, value_enum, default_value_t = OutputFormat::Human)]
+    pub format: OutputFormat,
+}
+
+#[derive(Debug, clap::Args)]
+pub struct GoldenArgs {
+    /// Directory searched for shader files
+    #[arg(default_value = "shaders")]
+    pub shaders: PathBuf,
+
+    /// Directory of the reference images, mirrors the shader directory
+    #[arg(long, default_value = "tests/golden")]
+    pub references: PathBuf,
+
+    /// Directory for the diff images of the failed shaders
+    #[arg(long, default_value = "target/golden-diff")]
+    pub diffs: PathBuf,
+
+    /// Largest allowed difference of a color channel, 0-255
+    #[arg(long, default_value_t = 2)]
+    pub tolerance: u8,
+
+    /// Write the rendered images as the new references instead of comparing
+    #[arg(long)]
+    pub bless: bool,
+
+    /// Image width in pixels
+    #[arg(long, default_value_t = 256)]
+    pub width: u32,
+
+    /// Image height in pixels
+    #[arg(long, default_value_t = 256)]
+    pub height: u32,
+
+    /// Value of `globals.time` in seconds
+    #[arg(long, default_value_t = 1.0)]
+    pub time: f32,
+
+    /// Value of `globals.frame`
+    #[arg(long, default_value_t = 60)]
+    pub frame: u32,
+
+    /// Run the shaders with the CPU interpreter instead of the GPU
+    #[arg(long)]
+    pub cpu: bool,
+}
+
+#[derive(Debug, clap::Args)]
+pub struct ImportArgs {
+    /// Shadertoy JSON export, any other file is read as a `mainImage` snippet
+    pub input: PathBuf,
+
+    /// Directory for the `.frag` files
+    #[arg(short, long, default_value = ".")]
+    pub output: PathBuf,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    /// A `file:line:col: error: message` line per diagnostic
+    Human,
+    Json,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Theme {
+    SolarizedDark,
+    Mocha,
+    Ocean,
+    Eighties,
+    InspiredGithub,
+}
+
+impl Theme {
+    pub fn ui(self) -> core::Theme {
+        match self {
+            Theme::SolarizedDark => core::Theme::SolarizedDark,
+            Theme::Mocha => core::Theme::CatppuccinMocha,
+            Theme::Ocean => core::Theme::Nord,
+            Theme::Eighties => core::Theme::Dark,
+            Theme::InspiredGithub => core::Theme::Light,
+        }
+    }
+
+    pub fn highlighter(self) -> highlighter::Theme {
+        match self {
+            Theme::SolarizedDark => highlighter::Theme::SolarizedDark,
+            Theme::Mocha => highlighter::Theme::Base16Mocha,
+            Theme::Ocean => highlighter::Theme::Base16Ocean,
+            Theme::Eighties => highlighter::Theme::Base16Eighties,
+            Theme::InspiredGithub => highlighter::Theme::InspiredGitHub,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum PresentMode {
+    AutoVsync,
+    AutoNoVsync,
+    Fifo,
+    FifoRelaxed,
+    Immediate,
+    Mailbox,
+}
+
+impl From<PresentMode> for wgpu::PresentMode {
+    fn from(present_mode: PresentMode) -> Self {
+        match present_mode {
+            PresentMode::AutoVsync => wgpu::PresentMode::AutoVsync,
+            PresentMode::AutoNoVsync => wgpu::PresentMode::AutoNoVsync,
+            PresentMode::Fifo => wgpu::PresentMode::Fifo,
+            PresentMode::FifoRelaxed => wgpu::PresentMode::FifoRelaxed,
+            PresentMode::Immediate => wgpu::PresentMode::Immediate,
+            PresentMode::Mailbox => wgpu::PresentMode::Mailbox,
+        }
+    }
+}
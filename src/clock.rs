@@ -0,0 +1,148 @@
+use std::time::Instant;
+
+/// Duration of a single frame step when paused, in seconds
+pub const STEP: f32 = 1.0 / 60.0;
+
+pub const SPEEDS: [Speed; 7] = [
+    Speed(0.1),
+    Speed(0.25),
+    Speed(0.5),
+    Speed(1.0),
+    Speed(2.0),
+    Speed(4.0),
+    Speed(8.0),
+];
+
+/// Playback speed multiplier
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Speed(pub f32);
+
+impl std::fmt::Display for Speed {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}x", self.0)
+    }
+}
+
+/// Time values of a single rendered frame
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Tick {
+    pub time: f32,
+    pub time_delta: f32,
+    pub frame: u32,
+}
+
+/// Playback time of the shader, driven by the render loop
+#[derive(Debug)]
+pub struct Clock {
+    playing: bool,
+    speed: Speed,
+    time: f32,
+    frame: u32,
+    last_tick: Option<Instant>,
+    pending_step: bool,
+}
+
+impl Default for Clock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Clock {
+    pub fn new() -> Self {
+        Clock {
+            playing: true,
+            speed: Speed(1.0),
+            time: 0.0,
+            frame: 0,
+            last_tick: None,
+            pending_step: false,
+        }
+    }
+
+    pub fn is_playing(&self) -> bool {
+        self.playing
+    }
+
+    pub fn time(&self) -> f32 {
+        self.time
+    }
+
+    pub fn frame(&self) -> u32 {
+        self.frame
+    }
+
+    pub fn speed(&self) -> Speed {
+        self.speed
+    }
+
+    pub fn toggle(&mut self) {
+        self.playing = !self.playing;
+        self.last_tick = None;
+    }
+
+    /// Pauses the playback and advances it by one [`STEP`] on the next tick
+    pub fn step(&mut self) {
+        self.playing = false;
+        self.last_tick = None;
+        self.pending_step = true;
+    }
+
+    pub fn set_speed(&mut self, speed: Speed) {
+        self.speed = speed;
+    }
+
+    pub fn seek(&mut self, time: f32) {
+        self.time = time.max(0.0);
+    }
+
+    pub fn restart(&mut self) {
+        self.time = 0.0;
+        self.frame = 0;
+        self.last_tick = None;
+    }
+
+    /// Time values of the current frame without advancing the clock,
+    /// `time_delta` is a single [`STEP`]
+    pub fn current(&self) -> Tick {
+        Tick {
+            time: self.time,
+            time_delta: STEP,
+            frame: self.frame,
+        }
+    }
+
+    /// Advances the clock, should be called once per rendered frame
+    pub fn tick(&mut self) -> Tick {
+        let time_delta = if self.playing {
+            let now = Instant::now();
+            let elapsed = self
+                .last_tick
+                .map(|last_tick| (now - last_tick).as_secs_f32())
+                .unwrap_or_default();
+            self.last_tick = Some(now);
+            elapsed * self.speed.0
+        } else if self.pending_step {
+            self.pending_step = false;
+            STEP * self.speed.0
+        } else {
+            0.0
+        };
+
+        let advancing = self.playing || time_delta > 0.0;
+        if advancing {
+            self.time += time_delta;
+        }
+
+        let tick = Tick {
+            time: self.time,
+            time_delta,
+            frame: self.frame,
+        };
+
+        if advancing {
+            self.frame = self.frame.wrapping_add(1);
+        }
+        tick
+    }
+}
@@ -1,83 +1,1383 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
 use iced_wgpu::Renderer;
-use iced_widget::{button, column, container, row, space, text, text_editor};
-use iced_winit::core::{Element, Length, Theme};
+use iced_widget::space::Space;
+use iced_widget::{
+    button, checkbox, column, container, pick_list, row, scrollable, slider, space, stack, text,
+    text_editor, text_input, tooltip,
+};
+use iced_winit::core::text::{LineHeight, Wrapping};
+use iced_winit::core::widget::operation::scrollable::AbsoluteOffset;
+use iced_winit::core::widget::{self, operation};
+use iced_winit::core::{Alignment, Color, Element, Font, Length, Pixels, Theme};
 use iced_winit::winit;
 use winit::event_loop::EventLoopProxy;
 
-use crate::{CustomEvent, SHADER_SOURCE, highlighter};
+use crate::clock::{self, Clock, Speed, Tick};
+use crate::diagnostic::Diagnostic;
+use crate::document::{Document, GutterBlock};
+use crate::entry_points::{EntryPoints, Selection};
+use crate::file_browser::{self, FileBrowser};
+use crate::pad::Pad;
+use crate::params::{Control, Param, Params, Scalar};
+use crate::recorder::Recording;
+use crate::scene::channels::{CHANNEL_COUNT, Channel, Filter, Source, Wrap};
+use crate::scene::compute::{Dispatch, STORAGE_COUNT};
+use crate::scene::graph::{PASS_COUNT, Pass};
+use crate::shadertoy;
+use crate::translation::{self, Target, Translation};
+use crate::validator::{BackgroundValidator, Validation};
+use crate::{CustomEvent, highlighter};
+
+/// Length of the timeline slider, it grows in these increments
+/// once the playback time goes past the end
+const TIMELINE_LENGTH: f32 = 60.0;
+
+/// Editor line height, shared with the gutter to keep the lines aligned
+const LINE_HEIGHT: f32 = 20.0;
+const EDITOR_PADDING: f32 = 5.0;
+const GUTTER_WIDTH: f32 = 44.0;
+/// Estimated advance of the monospace font, for the width of the lines
+const CHAR_WIDTH: f32 = 9.7;
+/// Width of the editor without long lines, the panel without the gutter
+const EDITOR_MIN_WIDTH: f32 = 440.0;
+const EDITOR_SCROLLABLE: widget::Id = widget::Id::new("editor");
 
 pub struct Controls {
     event_loop_proxy: EventLoopProxy<CustomEvent>,
-    content: text_editor::Content<Renderer>,
+    documents: Vec<Document>,
+    /// Tab shown in the editor
+    selected: usize,
+    /// Tab which is rendered, set by [`Message::UpdateShader`]
+    active: usize,
+    /// Tab with unsaved changes waiting for the close confirmation
+    closing: Option<usize>,
+    /// File of the active tab, followed by the file watcher
+    watched: Option<PathBuf>,
+    file_browser: Option<FileBrowser>,
+    file_error: Option<String>,
     editor_visible: bool,
-    shader_error: Option<String>,
+    theme: highlighter::Theme,
+    clock: Clock,
+    /// Offset the editor should be scrolled to
+    scroll_to: Option<AbsoluteOffset<Option<f32>>>,
+    /// Visible part of the editor, the text editor doesn't scroll by itself
+    editor_viewport: Option<scrollable::Viewport>,
+    validator: BackgroundValidator,
+    /// Render the selected tab as soon as it passes validation
+    auto_apply: bool,
+    /// Documents applied to the passes of the render graph
+    passes: [Option<u64>; PASS_COUNT],
+    channels: [[Channel; CHANNEL_COUNT]; PASS_COUNT],
+    channels_visible: bool,
+    dispatch: Dispatch,
+    /// Workgroup counts typed for [`Dispatch::Fixed`]
+    workgroups: [String; 3],
+    /// Entry points chosen for the files, restored when they are opened again
+    selections: HashMap<PathBuf, Selection>,
+    export_visible: bool,
+    /// Width and height typed for the exported frames
+    export_size: [String; 2],
+    /// Frame count and frame rate typed for the recording
+    recording: [String; 2],
+    record_progress: Option<[u32; 2]>,
+    /// Pipe the recorded frames to ffmpeg
+    record_video: bool,
+    /// Result of the last export
+    notice: Option<String>,
+    translation_visible: bool,
+    target: Target,
+    /// Entry point chosen for the translation, `None` for the rendered fragment one
+    translated_entry: Option<String>,
+    /// Read-only output of the backend
+    translation: text_editor::Content<Renderer>,
+    /// Lines scrolled down in the translation, kept when it's translated again
+    translation_scroll: i32,
+    translation_error: Option<String>,
+    /// Parameters declared by the shaders of the passes
+    pass_params: [Vec<Param>; PASS_COUNT],
+    /// Parameters shown in the panel and written to the buffer, see [`Controls::update_params`]
+    params: Params,
+    /// Passes whose parameters don't match the ones in the buffer
+    params_warning: Option<String>,
+    params_visible: bool,
 }
 
 #[derive(Debug, Clone)]
 pub enum Message {
     Edit(text_editor::Action),
+    EditorScrolled(scrollable::Viewport),
     UpdateShader,
     ToggleEditor,
-    ShaderError(String),
-    ShaderValid,
+    ShaderError(Diagnostic),
+    ShaderValid(EntryPoints),
+    TogglePlayback,
+    Step,
+    Restart,
+    SetSpeed(Speed),
+    Seek(f32),
+    New,
+    Open,
+    Save,
+    SaveAs,
+    FileBrowser(file_browser::Message),
+    SelectTab(usize),
+    CloseTab(usize),
+    ConfirmClose,
+    CancelClose,
+    GoToError,
+    Validated {
+        document: u64,
+        revision: u64,
+        result: Result<Arc<Validation>, Diagnostic>,
+    },
+    SelectVertex(String),
+    SelectFragment(String),
+    ToggleAutoApply(bool),
+    SetPass(Pass),
+    FileError(String),
+    ToggleChannels,
+    OpenChannel(usize),
+    SetChannelSource(usize, Source),
+    /// The channel of the pass was loaded, an image which failed keeps the previous source
+    ChannelUpdated(Pass, usize, Channel),
+    SetChannelFilter(usize, Filter),
+    SetChannelWrap(usize, Wrap),
+    SetDispatch(Dispatch),
+    SetWorkgroups(usize, String),
+    ToggleExport,
+    SetExportSize(usize, String),
+    /// Pick the file for the frame rendered at the export size
+    Export,
+    SetRecording(usize, String),
+    ToggleRecordVideo(bool),
+    /// Pick the file for the animation rendered at the export size
+    Record,
+    Exported(PathBuf),
+    /// Animation written, with the problems of the video encoder
+    Recorded(PathBuf, Vec<String>),
+    /// Rendered and total frames of the recording in progress, `None` once it's done
+    RecordProgress(Option<[u32; 2]>),
+    ToggleTranslation,
+    SetTarget(Target),
+    SelectTranslated(String),
+    TranslationAction(text_editor::Action),
+    CopyTranslation,
+    /// Pick the file for the translated shader
+    ExportTranslation,
+    /// Pick a Shadertoy shader to open as new tabs
+    Import,
+    /// Parameters of the shader applied to the pass
+    SetParams(Pass, Vec<Param>),
+    SetParam(usize, [f32; 4]),
+    ResetParams,
+    ToggleParams,
 }
 
 impl Controls {
-    pub fn new(event_loop_proxy: EventLoopProxy<CustomEvent>) -> Controls {
-        let content = text_editor::Content::with_text(SHADER_SOURCE);
-        Controls {
+    pub fn new(
+        event_loop_proxy: EventLoopProxy<CustomEvent>,
+        path: &Path,
+        editor_visible: bool,
+        theme: highlighter::Theme,
+    ) -> Controls {
+        let (document, file_error) = match Document::open(path) {
+            Ok(document) => (document, None),
+            Err(e) => (Document::untitled(), Some(e)),
+        };
+        // the image pass starts with the opened file
+        let mut passes = [None; PASS_COUNT];
+        passes[Pass::Image.index()] = Some(document.id);
+        let controls = Controls {
+            validator: BackgroundValidator::new(event_loop_proxy.clone()),
+            auto_apply: false,
+            passes,
+            channels: Default::default(),
+            channels_visible: false,
+            dispatch: Dispatch::default(),
+            workgroups: ["1".to_owned(), "1".to_owned(), "1".to_owned()],
+            selections: HashMap::new(),
+            export_visible: false,
+            export_size: ["1920".to_owned(), "1080".to_owned()],
+            recording: ["120".to_owned(), "60".to_owned()],
+            record_progress: None,
+            record_video: false,
+            notice: None,
+            translation_visible: false,
+            target: Target::default(),
+            translated_entry: None,
+            translation: text_editor::Content::new(),
+            translation_scroll: 0,
+            translation_error: None,
+            pass_params: Default::default(),
+            params: Params::default(),
+            params_warning: None,
+            params_visible: true,
             event_loop_proxy,
-            content,
-            editor_visible: true,
-            shader_error: None,
-        }
+            watched: document.path.clone(),
+            documents: vec![document],
+            selected: 0,
+            active: 0,
+            closing: None,
+            file_browser: None,
+            file_error,
+            editor_visible,
+            theme,
+            clock: Clock::new(),
+            scroll_to: None,
+            editor_viewport: None,
+        };
+        // finds the entry points of the opened file
+        controls.request_validation(0);
+        controls
+    }
+
+    pub fn is_playing(&self) -> bool {
+        self.clock.is_playing()
+    }
+
+    pub fn tick(&mut self) -> Tick {
+        self.clock.tick()
+    }
+
+    /// Widget operation requested by the last update, like scrolling the editor
+    pub fn take_operation(&mut self) -> Option<impl widget::Operation + use<>> {
+        let offset = self.scroll_to.take()?;
+        Some(operation::scrollable::scroll_to(EDITOR_SCROLLABLE, offset))
     }
 
     pub fn update(&mut self, message: Message) {
         match message {
             Message::Edit(action) => {
-                self.content.perform(action);
+                let is_edit = action.is_edit();
+                self.documents[self.selected].perform(action);
+                if is_edit {
+                    self.request_validation(self.selected);
+                }
+                self.follow_cursor();
+            }
+            Message::EditorScrolled(viewport) => {
+                self.editor_viewport = Some(viewport);
             }
             Message::UpdateShader => {
-                let shader_text = self.content.text();
-                let event = CustomEvent::UpdateShader(shader_text);
-                if let Err(e) = self.event_loop_proxy.send_event(event) {
-                    println!("Error sending UpdateShader event: {e}");
+                self.active = self.selected;
+                self.watch_active();
+                let document = &self.documents[self.active];
+                let (id, pass) = (document.id, document.pass);
+                let shader = document.shader();
+                let selection = document.selection.clone();
+                let validation = document.validation.clone();
+                // the document was moved to another pass
+                for other in Pass::ALL {
+                    if other != pass
+                        && other != Pass::Image
+                        && self.passes[other.index()] == Some(id)
+                    {
+                        self.remove_pass(other);
+                    }
                 }
+                self.passes[pass.index()] = Some(id);
+                self.send_event(CustomEvent::UpdateShader(
+                    pass, shader, selection, validation,
+                ));
+            }
+            Message::SetPass(pass) => {
+                self.documents[self.selected].pass = pass;
             }
             Message::ToggleEditor => {
                 self.editor_visible = !self.editor_visible;
             }
-            Message::ShaderError(e) => {
-                self.shader_error = Some(e);
+            Message::ShaderError(diagnostic) => {
+                self.documents[self.active].set_diagnostic(Some(diagnostic));
+            }
+            Message::ShaderValid(entry_points) => {
+                let document = &mut self.documents[self.active];
+                document.set_diagnostic(None);
+                document.entry_points = entry_points;
+            }
+            Message::TogglePlayback => {
+                self.clock.toggle();
+            }
+            Message::Step => {
+                self.clock.step();
+            }
+            Message::Restart => {
+                self.clock.restart();
+            }
+            Message::SetSpeed(speed) => {
+                self.clock.set_speed(speed);
+            }
+            Message::Seek(time) => {
+                self.clock.seek(time);
+            }
+            Message::New => {
+                self.documents.push(Document::untitled());
+                self.selected = self.documents.len() - 1;
+                self.file_browser = None;
+                self.file_error = None;
+                self.update(Message::UpdateShader);
+            }
+            Message::Open => {
+                self.file_browser = Some(FileBrowser::new(
+                    file_browser::Mode::Open,
+                    self.documents[self.selected].path.as_deref(),
+                ));
+            }
+            Message::Save => match self.documents[self.selected].path.clone() {
+                Some(path) => self.save(path),
+                None => self.update(Message::SaveAs),
+            },
+            Message::SaveAs => {
+                let document = &self.documents[self.selected];
+                let path = document
+                    .path
+                    .clone()
+                    .or_else(|| document.name.as_ref().map(PathBuf::from));
+                self.file_browser = Some(FileBrowser::new(
+                    file_browser::Mode::SaveAs,
+                    path.as_deref(),
+                ));
+            }
+            Message::SelectTab(index) => {
+                if index < self.documents.len() {
+                    self.selected = index;
+                    self.file_browser = None;
+                    self.translation_scroll = 0;
+                    self.translate();
+                }
+            }
+            Message::CloseTab(index) => {
+                if self
+                    .documents
+                    .get(index)
+                    .is_some_and(|document| document.dirty)
+                {
+                    self.closing = Some(index);
+                } else {
+                    self.close(index);
+                }
+            }
+            Message::ConfirmClose => {
+                if let Some(index) = self.closing.take() {
+                    self.close(index);
+                }
+            }
+            Message::CancelClose => {
+                self.closing = None;
+            }
+            Message::Validated {
+                document,
+                revision,
+                result,
+            } => {
+                let Some(index) = self.documents.iter().position(|d| d.id == document) else {
+                    return;
+                };
+                // spans of an outdated revision would point to the wrong text
+                if self.documents[index].revision != revision {
+                    return;
+                }
+                let is_valid = result.is_ok();
+                match result {
+                    Ok(validation) => {
+                        self.documents[index].set_diagnostic(None);
+                        self.documents[index].entry_points = validation.entry_points.clone();
+                        self.documents[index].validation = Some(validation);
+                    }
+                    Err(diagnostic) => self.documents[index].set_diagnostic(Some(diagnostic)),
+                }
+                if is_valid && index == self.selected {
+                    self.translate();
+                    if self.auto_apply {
+                        self.update(Message::UpdateShader);
+                    }
+                }
+            }
+            Message::SelectVertex(name) => {
+                self.documents[self.selected].selection.vertex = Some(name);
+                self.selection_changed();
+            }
+            Message::SelectFragment(name) => {
+                self.documents[self.selected].selection.fragment = Some(name);
+                self.selection_changed();
+            }
+            Message::ToggleAutoApply(auto_apply) => {
+                self.auto_apply = auto_apply;
+            }
+            Message::FileError(error) => {
+                self.file_error = Some(error);
+            }
+            Message::ToggleChannels => {
+                self.channels_visible = !self.channels_visible;
+            }
+            Message::OpenChannel(index) => {
+                let current = match &self.selected_channels()[index].source {
+                    Source::Image(path) => Some(path.as_path()),
+                    _ => None,
+                };
+                self.file_browser = Some(FileBrowser::new(
+                    file_browser::Mode::OpenImage(index),
+                    current,
+                ));
+            }
+            Message::SetChannelSource(index, source) => {
+                let pass = self.documents[self.selected].pass;
+                let mut channel = self.selected_channels()[index].clone();
+                channel.source = source;
+                self.send_event(CustomEvent::UpdateChannel(pass, index, channel));
+            }
+            Message::ChannelUpdated(pass, index, channel) => {
+                self.channels[pass.index()][index] = channel;
+            }
+            Message::SetChannelFilter(index, filter) => {
+                self.selected_channels_mut()[index].filter = filter;
+                self.update_channel(index);
+            }
+            Message::SetChannelWrap(index, wrap) => {
+                self.selected_channels_mut()[index].wrap = wrap;
+                self.update_channel(index);
+            }
+            Message::SetDispatch(dispatch) => {
+                self.dispatch = dispatch;
+                self.send_event(CustomEvent::SetDispatch(dispatch));
+            }
+            Message::SetWorkgroups(axis, count) => {
+                let parsed = count.trim().parse::<u32>();
+                self.workgroups[axis] = count;
+                if let (Ok(count), Dispatch::Fixed(mut workgroups)) = (parsed, self.dispatch) {
+                    workgroups[axis] = count;
+                    self.update(Message::SetDispatch(Dispatch::Fixed(workgroups)));
+                }
+            }
+            Message::ToggleExport => {
+                self.export_visible = !self.export_visible;
+            }
+            Message::SetExportSize(axis, size) => {
+                self.export_size[axis] = size;
+            }
+            Message::Export => {
+                let path = self.documents[self.selected]
+                    .path
+                    .as_ref()
+                    .map(|path| path.with_extension("png"));
+                self.file_browser = Some(FileBrowser::new(
+                    file_browser::Mode::ExportImage,
+                    path.as_deref(),
+                ));
+            }
+            Message::SetRecording(index, value) => {
+                self.recording[index] = value;
+            }
+            Message::ToggleRecordVideo(record_video) => {
+                self.record_video = record_video;
+            }
+            Message::Record => {
+                if self.record_progress.is_some() {
+                    return;
+                }
+                let path = self.documents[self.selected]
+                    .path
+                    .as_ref()
+                    .map(|path| path.with_extension("png"));
+                self.file_browser = Some(FileBrowser::new(
+                    file_browser::Mode::Record,
+                    path.as_deref(),
+                ));
+            }
+            Message::RecordProgress(progress) => {
+                self.record_progress = progress;
+            }
+            Message::Exported(path) => {
+                self.file_error = None;
+                self.notice = Some(format!("Exported {}", path.display()));
+            }
+            Message::Recorded(path, warnings) => {
+                self.file_error = None;
+                let mut notice = format!("Exported {}", path.display());
+                for warning in &warnings {
+                    notice.push_str(&format!("\n{warning}"));
+                }
+                self.notice = Some(notice);
+            }
+            Message::ToggleTranslation => {
+                self.translation_visible = !self.translation_visible;
+                self.translate();
+            }
+            Message::SetTarget(target) => {
+                self.target = target;
+                self.translation_scroll = 0;
+                self.translate();
+            }
+            Message::SelectTranslated(name) => {
+                self.translated_entry = Some(name);
+                self.translation_scroll = 0;
+                self.translate();
+            }
+            Message::TranslationAction(action) => {
+                // selection and scrolling only, the text is read-only
+                if let text_editor::Action::Scroll { lines } = action {
+                    let line_count = self.translation.line_count() as i32;
+                    self.translation_scroll =
+                        (self.translation_scroll + lines).clamp(0, line_count);
+                }
+                if !action.is_edit() {
+                    self.translation.perform(action);
+                }
+            }
+            Message::CopyTranslation => {
+                self.send_event(CustomEvent::CopyToClipboard(self.translation.text()));
+            }
+            Message::ExportTranslation => {
+                let document = &self.documents[self.selected];
+                let Some(entry_point) = self.translated_entry() else {
+                    return;
+                };
+                let stage = if document.entry_points.vertex.contains(&entry_point) {
+                    naga::ShaderStage::Vertex
+                } else if document.entry_points.compute.contains(&entry_point) {
+                    naga::ShaderStage::Compute
+                } else {
+                    naga::ShaderStage::Fragment
+                };
+                let extension = self.target.extension(stage);
+                let path = document
+                    .path
+                    .as_ref()
+                    .map(|path| path.with_extension(extension));
+                self.file_browser = Some(FileBrowser::new(
+                    file_browser::Mode::ExportTranslation(extension),
+                    path.as_deref(),
+                ));
+            }
+            Message::SetParams(pass, layout) => {
+                self.pass_params[pass.index()] = layout;
+                self.update_params();
+            }
+            Message::SetParam(index, value) => {
+                self.params.set(index, value);
+            }
+            Message::ResetParams => {
+                self.params.reset();
+            }
+            Message::ToggleParams => {
+                self.params_visible = !self.params_visible;
+            }
+            Message::Import => {
+                self.file_browser = Some(FileBrowser::new(file_browser::Mode::Import, None));
+            }
+            Message::GoToError => {
+                let document = &mut self.documents[self.selected];
+                if let Some((line, column)) = document.go_to_error() {
+                    // keep a few lines above the error visible
+                    let column = document.char_column(line, column);
+                    self.scroll_to = Some(AbsoluteOffset {
+                        x: Some(column.saturating_sub(8) as f32 * CHAR_WIDTH),
+                        y: Some(line.saturating_sub(3) as f32 * LINE_HEIGHT),
+                    });
+                }
+            }
+            Message::FileBrowser(message) => {
+                let Some(file_browser) = &mut self.file_browser else {
+                    return;
+                };
+                match file_browser.update(message) {
+                    Some(file_browser::Event::Open(path)) => {
+                        self.file_browser = None;
+                        self.open(path);
+                    }
+                    Some(file_browser::Event::SaveAs(path)) => {
+                        self.file_browser = None;
+                        self.save(path);
+                    }
+                    Some(file_browser::Event::OpenImage(index, path)) => {
+                        self.file_browser = None;
+                        self.file_error = None;
+                        self.update(Message::SetChannelSource(index, Source::Image(path)));
+                    }
+                    Some(file_browser::Event::ExportImage(path)) => {
+                        self.file_browser = None;
+                        self.notice = None;
+                        match self.export_size() {
+                            Ok([width, height]) => self.send_event(CustomEvent::ExportImage {
+                                path,
+                                width,
+                                height,
+                                tick: self.clock.current(),
+                            }),
+                            Err(e) => self.file_error = Some(e),
+                        }
+                    }
+                    Some(file_browser::Event::Record(path)) => {
+                        self.file_browser = None;
+                        self.notice = None;
+                        match self.recording(path) {
+                            Ok(recording) => self.send_event(CustomEvent::Record(recording)),
+                            Err(e) => self.file_error = Some(e),
+                        }
+                    }
+                    Some(file_browser::Event::ExportTranslation(path)) => {
+                        self.file_browser = None;
+                        self.notice = None;
+                        match self.export_translation(&path) {
+                            Ok(()) => self.update(Message::Exported(path)),
+                            Err(e) => self.file_error = Some(e),
+                        }
+                    }
+                    Some(file_browser::Event::Import(path)) => {
+                        self.file_browser = None;
+                        self.import(&path);
+                    }
+                    Some(file_browser::Event::Cancel) => {
+                        self.file_browser = None;
+                    }
+                    None => (),
+                }
+            }
+        }
+    }
+
+    /// Scrolls the editor to keep the cursor of the selected document visible
+    fn follow_cursor(&mut self) {
+        let Some(viewport) = self.editor_viewport else {
+            return;
+        };
+        let document = &self.documents[self.selected];
+        let position = document.content.cursor().position;
+        let column = document.char_column(position.line, position.column);
+        let offset = viewport.absolute_offset();
+        let bounds = viewport.bounds();
+
+        let top = EDITOR_PADDING + position.line as f32 * LINE_HEIGHT;
+        let y = if top < offset.y {
+            Some(top - EDITOR_PADDING)
+        } else if top + LINE_HEIGHT > offset.y + bounds.height {
+            Some(top + LINE_HEIGHT + EDITOR_PADDING - bounds.height)
+        } else {
+            None
+        };
+        // the gutter scrolls together with the text
+        let left = GUTTER_WIDTH + EDITOR_PADDING + column as f32 * CHAR_WIDTH;
+        let x = if left < offset.x + GUTTER_WIDTH + CHAR_WIDTH {
+            Some((left - GUTTER_WIDTH - 4.0 * CHAR_WIDTH).max(0.0))
+        } else if left + CHAR_WIDTH > offset.x + bounds.width {
+            Some(left + 4.0 * CHAR_WIDTH - bounds.width)
+        } else {
+            None
+        };
+        if x.is_some() || y.is_some() {
+            self.scroll_to = Some(AbsoluteOffset { x, y });
+        }
+    }
+
+    fn remove_pass(&mut self, pass: Pass) {
+        self.passes[pass.index()] = None;
+        self.send_event(CustomEvent::RemovePass(pass));
+        self.update(Message::SetParams(pass, Vec::new()));
+    }
+
+    /// Parameters of the rendered passes, written to the uniform buffer every frame
+    pub fn params(&self) -> &Params {
+        &self.params
+    }
+
+    /// The passes share the buffer, the parameters of the image pass are used,
+    /// or of the first pass declaring any. The other passes read them as well,
+    /// the ones declaring a different struct get a warning.
+    fn update_params(&mut self) {
+        let layout = std::iter::once(Pass::Image)
+            .chain(Pass::ALL)
+            .map(|pass| &self.pass_params[pass.index()])
+            .find(|layout| !layout.is_empty())
+            .cloned()
+            .unwrap_or_default();
+        let differing: Vec<_> = Pass::ALL
+            .into_iter()
+            .filter(|pass| {
+                let other = &self.pass_params[pass.index()];
+                !other.is_empty()
+                    && (other.len() != layout.len()
+                        || other.iter().zip(&layout).any(|(a, b)| !a.same_member(b)))
+            })
+            .map(|pass| pass.to_string())
+            .collect();
+        self.params_warning = (!differing.is_empty()).then(|| {
+            format!(
+                "The parameters of {} differ, the buffer has the ones shown here",
+                differing.join(", ")
+            )
+        });
+        self.params.set_layout(layout);
+    }
+
+    fn send_event(&self, event: CustomEvent) {
+        if let Err(e) = self.event_loop_proxy.send_event(event) {
+            println!("Error sending custom winit event: {e}");
+        }
+    }
+
+    /// Pass of the document applied last, followed by the file watcher
+    pub fn active_shader(&self) -> (Pass, Selection) {
+        let document = &self.documents[self.active];
+        let pass = Pass::ALL
+            .into_iter()
+            .find(|pass| self.passes[pass.index()] == Some(document.id))
+            .unwrap_or_default();
+        (pass, document.selection.clone())
+    }
+
+    /// Renders with the entry points chosen for the selected file
+    fn selection_changed(&mut self) {
+        self.remember_selection();
+        if self
+            .passes
+            .contains(&Some(self.documents[self.selected].id))
+        {
+            self.update(Message::UpdateShader);
+        }
+    }
+
+    fn remember_selection(&mut self) {
+        let document = &self.documents[self.selected];
+        if let Some(path) = &document.path {
+            self.selections
+                .insert(path.clone(), document.selection.clone());
+        }
+    }
+
+    /// Entry point of the selected document shown in the translation panel,
+    /// the chosen one if the shader still has it, otherwise the rendered fragment
+    /// entry point or the first compute one
+    fn translated_entry(&self) -> Option<String> {
+        let document = &self.documents[self.selected];
+        let entry_points = &document.entry_points;
+        let all = entry_points
+            .vertex
+            .iter()
+            .chain(&entry_points.fragment)
+            .chain(&entry_points.compute);
+        self.translated_entry
+            .clone()
+            .filter(|name| all.clone().any(|candidate| candidate == name))
+            .or_else(|| entry_points.resolve(&document.selection).fragment)
+            .or_else(|| entry_points.compute.first().cloned())
+    }
+
+    /// Translates the selected document while the panel is shown
+    fn translate(&mut self) {
+        if !self.translation_visible {
+            return;
+        }
+        match self.translation() {
+            Ok(translation) => {
+                // a new content starts at the top, scroll back to where the reader was
+                if translation.text != self.translation.text() {
+                    self.translation = text_editor::Content::with_text(&translation.text);
+                    self.translation.perform(text_editor::Action::Scroll {
+                        lines: self.translation_scroll,
+                    });
+                }
+                self.translation_error = None;
+            }
+            Err(e) => self.translation_error = Some(e),
+        }
+    }
+
+    /// Translation of the selected document, from the module of its background
+    /// validation when it's up to date
+    fn translation(&self) -> Result<Translation, String> {
+        let entry_point = self
+            .translated_entry()
+            .ok_or_else(|| "The shader has no entry points".to_owned())?;
+        let document = &self.documents[self.selected];
+        match &document.validation {
+            Some(validation) => translation::translate_module(
+                &validation.module,
+                &validation.info,
+                self.target,
+                &entry_point,
+            ),
+            None => translation::translate(&document.shader(), self.target, &entry_point),
+        }
+    }
+
+    fn export_translation(&self, path: &Path) -> Result<(), String> {
+        let translation = self.translation()?;
+        std::fs::write(path, translation.file)
+            .map_err(|e| format!("Error writing {}: {e}", path.display()))
+    }
+
+    /// Validates the document in the background to find its entry points
+    fn request_validation(&self, index: usize) {
+        let document = &self.documents[index];
+        self.validator
+            .request(document.id, document.revision, document.shader());
+    }
+
+    /// Channels of the pass of the selected document
+    fn selected_channels(&self) -> &[Channel; CHANNEL_COUNT] {
+        &self.channels[self.documents[self.selected].pass.index()]
+    }
+
+    fn selected_channels_mut(&mut self) -> &mut [Channel; CHANNEL_COUNT] {
+        &mut self.channels[self.documents[self.selected].pass.index()]
+    }
+
+    fn update_channel(&self, index: usize) {
+        let pass = self.documents[self.selected].pass;
+        self.send_event(CustomEvent::UpdateChannel(
+            pass,
+            index,
+            self.selected_channels()[index].clone(),
+        ));
+    }
+
+    /// Points the file watcher to the file of the active tab
+    fn watch_active(&mut self) {
+        let path = self.documents[self.active].path.clone();
+        if path != self.watched {
+            self.watched = path.clone();
+            self.send_event(CustomEvent::WatchFile(path));
+        }
+    }
+
+    fn open(&mut self, path: PathBuf) {
+        let existing = self
+            .documents
+            .iter()
+            .position(|document| document.path.as_ref() == Some(&path));
+        if let Some(index) = existing {
+            self.selected = index;
+            return;
+        }
+
+        match Document::open(&path) {
+            Ok(mut document) => {
+                if let Some(selection) = self.selections.get(&path) {
+                    document.selection = selection.clone();
+                }
+                self.documents.push(document);
+                self.selected = self.documents.len() - 1;
+                self.request_validation(self.selected);
+                self.file_error = None;
+                self.update(Message::UpdateShader);
+            }
+            Err(e) => {
+                self.file_error = Some(e);
+            }
+        }
+    }
+
+    /// Opens every pass in a new tab, binds the channels and renders the passes
+    fn import(&mut self, path: &Path) {
+        let import = match shadertoy::import(path) {
+            Ok(import) => import,
+            Err(e) => {
+                self.file_error = Some(e);
+                return;
+            }
+        };
+        let stem = path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        // the image pass is the last one and stays selected
+        for imported in import.passes {
+            let mut document =
+                Document::imported(format!("{stem}_{}.frag", imported.name), &imported.source);
+            document.pass = imported.pass;
+            self.documents.push(document);
+            self.selected = self.documents.len() - 1;
+            self.channels[imported.pass.index()] = imported.channels;
+            for index in 0..CHANNEL_COUNT {
+                self.update_channel(index);
+            }
+            self.request_validation(self.selected);
+            self.update(Message::UpdateShader);
+        }
+
+        self.file_error = None;
+        let mut notice = format!("Imported {}", import.name);
+        for warning in &import.warnings {
+            notice.push_str(&format!("\n{warning}"));
+        }
+        self.notice = Some(notice);
+    }
+
+    fn save(&mut self, path: PathBuf) {
+        match self.documents[self.selected].save(path) {
+            Ok(()) => {
+                self.file_error = None;
+                self.remember_selection();
+                self.watch_active();
+                // the extension may change the language
+                self.request_validation(self.selected);
+            }
+            Err(e) => {
+                self.file_error = Some(e);
+            }
+        }
+    }
+
+    fn close(&mut self, index: usize) {
+        if index >= self.documents.len() {
+            return;
+        }
+        let id = self.documents[index].id;
+        let was_image = self.passes[Pass::Image.index()] == Some(id);
+        for pass in Pass::ALL {
+            if pass != Pass::Image && self.passes[pass.index()] == Some(id) {
+                self.remove_pass(pass);
+            }
+        }
+        self.documents.remove(index);
+        self.closing = None;
+        if self.documents.is_empty() {
+            self.documents.push(Document::untitled());
+        }
+
+        if self.selected > index || self.selected == self.documents.len() {
+            self.selected -= 1;
+        }
+        if self.active > index || self.active == self.documents.len() {
+            self.active -= 1;
+        }
+        if was_image {
+            // the rendered tab is gone, render the one left in the editor if it's
+            // for the image pass, otherwise the image pass goes back to the template
+            if self.documents[self.selected].pass == Pass::Image {
+                self.update(Message::UpdateShader);
+            } else {
+                self.remove_pass(Pass::Image);
             }
-            Message::ShaderValid => {
-                self.shader_error = None;
+        }
+    }
+
+    fn tab_bar(&self) -> Element<'_, Message, Theme, Renderer> {
+        let tabs = self.documents.iter().enumerate().map(|(index, document)| {
+            let pass = Pass::ALL
+                .into_iter()
+                .find(|pass| self.passes[pass.index()] == Some(document.id));
+            let title = match pass {
+                Some(pass) => format!("{}> {}", pass.short_name(), document.title()),
+                None => document.title(),
+            };
+            let style = if index == self.selected {
+                button::primary
+            } else {
+                button::secondary
+            };
+            row![
+                button(text(title))
+                    .on_press(Message::SelectTab(index))
+                    .style(style),
+                button("x").on_press(Message::CloseTab(index)).style(style),
+            ]
+            .into()
+        });
+
+        scrollable(row(tabs).spacing(2).padding(1))
+            .direction(scrollable::Direction::Horizontal(
+                scrollable::Scrollbar::new().width(4).scroller_width(4),
+            ))
+            .into()
+    }
+
+    /// Line numbers with error markers, the message is shown on hover
+    fn gutter<'a>(&self, document: &'a Document) -> Element<'a, Message, Theme, Renderer> {
+        let blocks = document.gutter.iter().map(|block| {
+            let numbers: Element<'a, Message, Theme, Renderer> = match block {
+                GutterBlock::Lines(numbers) => text(numbers)
+                    .size(12)
+                    .line_height(LineHeight::Absolute(Pixels(LINE_HEIGHT)))
+                    .style(text::secondary)
+                    .into(),
+                GutterBlock::Error(number, message) => tooltip(
+                    text(number)
+                        .size(12)
+                        .line_height(LineHeight::Absolute(Pixels(LINE_HEIGHT)))
+                        .style(text::danger),
+                    container(text(message).font(Font::MONOSPACE))
+                        .padding(4)
+                        .style(container::rounded_box),
+                    tooltip::Position::Right,
+                )
+                .into(),
+            };
+            container(numbers)
+                .width(Length::Fill)
+                .align_right(Length::Fill)
+                .padding([0.0, 6.0])
+                .into()
+        });
+
+        column(blocks)
+            .width(GUTTER_WIDTH)
+            .padding([EDITOR_PADDING, 0.0])
+            .into()
+    }
+
+    /// Red background of the lines with errors, drawn under the text editor
+    fn error_marks<'a>(&self, document: &'a Document) -> Element<'a, Message, Theme, Renderer> {
+        let mut lines: Vec<usize> = document.error_lines.iter().map(|(line, _)| *line).collect();
+        lines.sort_unstable();
+        lines.dedup();
+
+        let mut marks = column![].padding([EDITOR_PADDING, 0.0]);
+        let mut next = 0;
+        for line in lines {
+            marks = marks
+                .push(Space::new().height((line - next) as f32 * LINE_HEIGHT))
+                .push(
+                    container(Space::new().width(Length::Fill).height(LINE_HEIGHT))
+                        .style(error_line),
+                );
+            next = line + 1;
+        }
+        container(marks)
+            .width(Length::Fill)
+            .height(Length::Fill)
+            .style(editor_background)
+            .into()
+    }
+
+    fn channel_list(&self) -> Element<'_, Message, Theme, Renderer> {
+        let pass = self.documents[self.selected].pass;
+        let rows = self
+            .selected_channels()
+            .iter()
+            .enumerate()
+            .map(|(index, channel)| {
+                let mut sources = vec![Source::None];
+                if let Source::Image(_) = &channel.source {
+                    sources.push(channel.source.clone());
+                }
+                sources.extend(Pass::BUFFERS.map(Source::Buffer));
+                // the compute pass writes the storage textures, it can't sample them
+                if pass != Pass::Compute {
+                    sources.extend((0..STORAGE_COUNT).map(Source::Storage));
+                }
+                row![
+                    text(format!("iChannel{index}")).width(80),
+                    pick_list(sources, Some(channel.source.clone()), move |source| {
+                        Message::SetChannelSource(index, source)
+                    })
+                    .width(Length::Fill),
+                    button("Image")
+                        .on_press(Message::OpenChannel(index))
+                        .style(button::secondary),
+                    pick_list(Filter::ALL, Some(channel.filter), move |filter| {
+                        Message::SetChannelFilter(index, filter)
+                    }),
+                    pick_list(Wrap::ALL, Some(channel.wrap), move |wrap| {
+                        Message::SetChannelWrap(index, wrap)
+                    }),
+                ]
+                .spacing(4)
+                .align_y(Alignment::Center)
+                .into()
+            });
+        let mut column = column![text(format!("Inputs of {pass}"))]
+            .extend(rows)
+            .spacing(1)
+            .padding(1);
+        if pass == Pass::Compute {
+            column = column.push(self.dispatch_row());
+        }
+        column.into()
+    }
+
+    fn params_panel(&self) -> Element<'_, Message, Theme, Renderer> {
+        let rows = self
+            .params
+            .iter()
+            .enumerate()
+            .map(|(index, (param, value))| {
+                let control = match param.control {
+                    Control::Checkbox => checkbox(value[0] != 0.0)
+                        .on_toggle(move |checked| {
+                            Message::SetParam(index, [f32::from(u8::from(checked)); 4])
+                        })
+                        .into(),
+                    Control::Pad => row![
+                        Pad::new(param.min, param.max, [value[0], value[1]], move |[x, y]| {
+                            Message::SetParam(index, [x, y, 0.0, 0.0])
+                        }),
+                        text(format!("{:.3}, {:.3}", value[0], value[1])),
+                    ]
+                    .spacing(8)
+                    .into(),
+                    Control::Color => {
+                        let [r, g, b, a] = value;
+                        let alpha = if param.components == 4 { a } else { 1.0 };
+                        let swatch =
+                            container(Space::new().width(24).height(24)).style(move |_: &Theme| {
+                                container::background(Color::from_rgba(r, g, b, alpha))
+                            });
+                        row![swatch, param_sliders(index, param, value)]
+                            .spacing(4)
+                            .align_y(Alignment::Center)
+                            .into()
+                    }
+                    Control::Slider => param_sliders(index, param, value),
+                };
+                row![text(&param.name).width(80), control]
+                    .spacing(4)
+                    .align_y(Alignment::Center)
+                    .into()
+            });
+        column![
+            row![
+                text("Parameters"),
+                space::horizontal(),
+                button("Reset")
+                    .on_press(Message::ResetParams)
+                    .style(button::secondary),
+            ]
+            .align_y(Alignment::Center)
+        ]
+        .push(
+            self.params_warning
+                .as_ref()
+                .map(|warning| text(warning).style(text::danger)),
+        )
+        .extend(rows)
+        .spacing(1)
+        .padding(1)
+        .into()
+    }
+
+    fn dispatch_row(&self) -> Element<'_, Message, Theme, Renderer> {
+        let fixed = Dispatch::Fixed(
+            self.workgroups
+                .each_ref()
+                .map(|count| count.trim().parse().unwrap_or(1)),
+        );
+        let mut row = row![
+            text("Dispatch").width(80),
+            pick_list(
+                [Dispatch::Resolution, fixed],
+                Some(self.dispatch),
+                Message::SetDispatch
+            ),
+        ]
+        .spacing(4)
+        .align_y(Alignment::Center);
+        if let Dispatch::Fixed(_) = self.dispatch {
+            for (axis, count) in self.workgroups.iter().enumerate() {
+                row = row.push(
+                    text_input(["x", "y", "z"][axis], count)
+                        .on_input(move |count| Message::SetWorkgroups(axis, count))
+                        .width(60),
+                );
             }
         }
+        row.into()
+    }
+
+    fn export_size(&self) -> Result<[u32; 2], String> {
+        let [width, height] = &self.export_size;
+        match (width.trim().parse(), height.trim().parse()) {
+            (Ok(width), Ok(height)) => Ok([width, height]),
+            _ => Err(format!("Invalid export size {width}x{height}")),
+        }
+    }
+
+    fn recording(&self, path: PathBuf) -> Result<Recording, String> {
+        let [width, height] = self.export_size()?;
+        let [frames, fps] = &self.recording;
+        match (frames.trim().parse(), fps.trim().parse()) {
+            (Ok(frames), Ok(fps)) if frames > 0 && fps > 0 => Ok(Recording {
+                path,
+                width,
+                height,
+                frames,
+                fps,
+                video: self.record_video,
+            }),
+            _ => Err(format!("Invalid recording of {frames} frames at {fps} fps")),
+        }
+    }
+
+    fn export_row(&self) -> Element<'_, Message, Theme, Renderer> {
+        let record_row = row![
+            text("Frames").width(80),
+            text_input("frames", &self.recording[0])
+                .on_input(|frames| Message::SetRecording(0, frames))
+                .width(60),
+            text("fps"),
+            text_input("fps", &self.recording[1])
+                .on_input(|fps| Message::SetRecording(1, fps))
+                .width(60),
+            checkbox(self.record_video)
+                .label("ffmpeg")
+                .on_toggle(Message::ToggleRecordVideo),
+            space::horizontal(),
+            match self.record_progress {
+                Some([frame, frames]) => Element::from(text(format!("Frame {frame} / {frames}"))),
+                None => button("Record")
+                    .on_press(Message::Record)
+                    .style(button::secondary)
+                    .into(),
+            },
+        ]
+        .spacing(4)
+        .align_y(Alignment::Center);
+        let size_row = row![
+            text("Size").width(80),
+            text_input("width", &self.export_size[0])
+                .on_input(|width| Message::SetExportSize(0, width))
+                .width(60),
+            text("x"),
+            text_input("height", &self.export_size[1])
+                .on_input(|height| Message::SetExportSize(1, height))
+                .width(60),
+            space::horizontal(),
+            button("Export PNG")
+                .on_press(Message::Export)
+                .style(button::secondary),
+        ]
+        .spacing(4)
+        .align_y(Alignment::Center);
+        column![size_row, record_row].spacing(4).into()
+    }
+
+    fn translation_panel(&self) -> Element<'_, Message, Theme, Renderer> {
+        let entry_points = &self.documents[self.selected].entry_points;
+        let names: Vec<String> = entry_points
+            .vertex
+            .iter()
+            .chain(&entry_points.fragment)
+            .chain(&entry_points.compute)
+            .cloned()
+            .collect();
+        let header = row![
+            pick_list(Target::ALL, Some(self.target), Message::SetTarget),
+            pick_list(names, self.translated_entry(), Message::SelectTranslated),
+            space::horizontal(),
+            button("Copy")
+                .on_press(Message::CopyTranslation)
+                .style(button::secondary),
+            button("Export")
+                .on_press(Message::ExportTranslation)
+                .style(button::secondary),
+        ]
+        .spacing(4)
+        .align_y(Alignment::Center);
+        let output = text_editor(&self.translation)
+            .on_action(Message::TranslationAction)
+            .font(Font::MONOSPACE)
+            .size(12)
+            .height(250);
+        let mut column = column![header, output].spacing(4).padding(1);
+        if let Some(error) = &self.translation_error {
+            column = column.push(text(error).font(Font::MONOSPACE).style(text::danger));
+        }
+        column.into()
+    }
+
+    /// Entry points of the document, the compute pass runs every one of them
+    fn entry_points<'a>(&self, document: &'a Document) -> Element<'a, Message, Theme, Renderer> {
+        let entry_points = &document.entry_points;
+        if document.pass == Pass::Compute {
+            return text(entry_points.compute.join(", ")).size(12).into();
+        }
+        let resolved = entry_points.resolve(&document.selection);
+        row![
+            pick_list(
+                entry_points.vertex.as_slice(),
+                resolved.vertex,
+                Message::SelectVertex
+            )
+            .text_size(12),
+            pick_list(
+                entry_points.fragment.as_slice(),
+                resolved.fragment,
+                Message::SelectFragment
+            )
+            .text_size(12),
+        ]
+        .spacing(4)
+        .into()
     }
 
     pub fn view(&self) -> Element<'_, Message, Theme, Renderer> {
+        let document = &self.documents[self.selected];
         let position = {
-            let cursor = self.content.cursor();
+            let position = document.content.cursor().position;
             text(format!(
                 "{}:{}",
-                cursor.position.line + 1,
-                cursor.position.column + 1
+                position.line + 1,
+                document.char_column(position.line, position.column) + 1
             ))
         };
 
-        let editor = text_editor(&self.content)
+        // without wrapping the gutter stays aligned with the lines
+        let editor_width = (document.longest_line as f32 * CHAR_WIDTH + 2.0 * EDITOR_PADDING)
+            .max(EDITOR_MIN_WIDTH);
+        let editor = text_editor(&document.content)
             .on_action(Message::Edit)
+            .font(Font::MONOSPACE)
+            .wrapping(Wrapping::None)
+            .width(editor_width)
+            .style(transparent_editor)
+            .line_height(LineHeight::Absolute(Pixels(LINE_HEIGHT)))
+            .padding(EDITOR_PADDING)
             .highlight_with::<highlighter::Highlighter>(
                 highlighter::Settings {
-                    theme: highlighter::Theme::SolarizedDark,
-                    token: "wgsl".to_owned(),
+                    theme: self.theme,
+                    token: document.language.token().to_owned(),
+                    errors: document.error_lines.clone(),
                 },
                 |highlight, _theme| highlight.to_format(),
             );
 
-        let status_bar = row![space::horizontal(), position];
+        // the editor grows with its content and scrolls together with the gutter
+        let editor = stack![editor].push_under(self.error_marks(document));
+        let editor = scrollable(row![self.gutter(document), editor])
+            .direction(scrollable::Direction::Both {
+                vertical: scrollable::Scrollbar::default(),
+                horizontal: scrollable::Scrollbar::default(),
+            })
+            .on_scroll(Message::EditorScrolled)
+            .id(EDITOR_SCROLLABLE)
+            .height(Length::Fill);
+
+        let status_bar = row![
+            text(document.title()),
+            space::horizontal(),
+            self.entry_points(document),
+            pick_list(Pass::ALL, Some(document.pass), Message::SetPass).text_size(12),
+            position,
+        ]
+        .spacing(8)
+        .align_y(Alignment::Center);
+
+        let file_buttons = row![
+            button("New")
+                .on_press(Message::New)
+                .width(Length::Fill)
+                .style(button::secondary),
+            button("Open")
+                .on_press(Message::Open)
+                .width(Length::Fill)
+                .style(button::secondary),
+            button("Save")
+                .on_press(Message::Save)
+                .width(Length::Fill)
+                .style(button::secondary),
+            button("Save As")
+                .on_press(Message::SaveAs)
+                .width(Length::Fill)
+                .style(button::secondary),
+            button("Import")
+                .on_press(Message::Import)
+                .width(Length::Fill)
+                .style(button::secondary),
+        ]
+        .spacing(1)
+        .padding(1);
 
         let control_buttons = row![
             button("Toggle editor")
@@ -88,23 +1388,165 @@ impl Controls {
                 .on_press(Message::UpdateShader)
                 .width(Length::Fill)
                 .style(button::secondary),
+            button("Channels")
+                .on_press(Message::ToggleChannels)
+                .width(Length::Fill)
+                .style(button::secondary),
+            button("Params")
+                .on_press(Message::ToggleParams)
+                .width(Length::Fill)
+                .style(button::secondary),
+            button("Export")
+                .on_press(Message::ToggleExport)
+                .width(Length::Fill)
+                .style(button::secondary),
+            button("Translate")
+                .on_press(Message::ToggleTranslation)
+                .width(Length::Fill)
+                .style(button::secondary),
+            checkbox(self.auto_apply)
+                .label("Auto-apply")
+                .on_toggle(Message::ToggleAutoApply),
         ]
         .spacing(1)
-        .padding(1);
+        .padding(1)
+        .align_y(Alignment::Center);
+
+        let time = self.clock.time();
+        let timeline_end = ((time / TIMELINE_LENGTH).floor() + 1.0) * TIMELINE_LENGTH;
+        let playback = row![
+            button(if self.clock.is_playing() {
+                "Pause"
+            } else {
+                "Play"
+            })
+            .on_press(Message::TogglePlayback)
+            .style(button::secondary),
+            button("Step")
+                .on_press(Message::Step)
+                .style(button::secondary),
+            button("Restart")
+                .on_press(Message::Restart)
+                .style(button::secondary),
+            pick_list(clock::SPEEDS, Some(self.clock.speed()), Message::SetSpeed),
+            slider(0.0..=timeline_end, time, Message::Seek).step(clock::STEP),
+            text(format!("{time:.2}s #{}", self.clock.frame())),
+        ]
+        .spacing(4)
+        .padding(1)
+        .align_y(Alignment::Center);
+
+        let mut column = column![file_buttons, control_buttons, playback];
 
-        let mut column = column![control_buttons];
+        if self.channels_visible {
+            column = column.push(self.channel_list());
+        }
+
+        if self.params_visible && !self.params.is_empty() {
+            column = column.push(self.params_panel());
+        }
+
+        if self.export_visible {
+            column = column.push(self.export_row());
+        }
+
+        if self.translation_visible {
+            column = column.push(self.translation_panel());
+        }
 
-        if self.editor_visible {
+        if let Some(file_browser) = &self.file_browser {
+            column = column.push(file_browser.view().map(Message::FileBrowser));
+        } else if self.editor_visible {
+            column = column.push(self.tab_bar());
+            if let Some(index) = self.closing {
+                column = column.push(
+                    row![
+                        text(format!(
+                            "{} has unsaved changes",
+                            self.documents[index].title()
+                        )),
+                        space::horizontal(),
+                        button("Cancel")
+                            .on_press(Message::CancelClose)
+                            .style(button::secondary),
+                        button("Close")
+                            .on_press(Message::ConfirmClose)
+                            .style(button::danger),
+                    ]
+                    .spacing(4)
+                    .align_y(Alignment::Center),
+                );
+            }
             column = column.push(editor).push(status_bar);
         }
-        if let Some(error) = &self.shader_error {
+        if let Some(error) = &self.file_error {
             column = column.push(text(error));
         }
+        if let Some(notice) = &self.notice {
+            column = column.push(text(notice));
+        }
+        if document.diagnostic.is_some() {
+            column = column.push(
+                button(text(&document.report).font(Font::MONOSPACE))
+                    .on_press(Message::GoToError)
+                    .style(button::text)
+                    .padding(0),
+            );
+        }
 
         container(column).width(500).style(add_background).into()
     }
 }
 
+/// A slider per component, Ctrl+click restores the default value
+fn param_sliders<'a>(
+    index: usize,
+    param: &Param,
+    value: [f32; 4],
+) -> Element<'a, Message, Theme, Renderer> {
+    let sliders = (0..param.components).map(|component| {
+        let on_change = move |changed| {
+            let mut value = value;
+            value[component] = changed;
+            Message::SetParam(index, value)
+        };
+        let label = match param.scalar {
+            Scalar::F32 => format!("{:.3}", value[component]),
+            Scalar::I32 | Scalar::U32 => format!("{}", value[component].round()),
+        };
+        row![
+            slider(param.min..=param.max, value[component], on_change)
+                .step(param.step())
+                .default(param.default[component]),
+            text(label).width(60),
+        ]
+        .spacing(4)
+        .align_y(Alignment::Center)
+        .into()
+    });
+    column(sliders).spacing(1).into()
+}
+
+/// The background is drawn by [`Controls::error_marks`] under the text
+fn transparent_editor(theme: &Theme, status: text_editor::Status) -> text_editor::Style {
+    text_editor::Style {
+        background: Color::TRANSPARENT.into(),
+        ..text_editor::default(theme, status)
+    }
+}
+
+fn editor_background(theme: &Theme) -> container::Style {
+    theme.extended_palette().background.base.color.into()
+}
+
+fn error_line(theme: &Theme) -> container::Style {
+    Color {
+        a: 0.25,
+        ..theme.extended_palette().danger.base.color
+    }
+    .into()
+}
+
 fn add_background(theme: &Theme) -> container::Style {
     theme.palette().background.into()
 }
@@ -0,0 +1,214 @@
+use std::ops::Range;
+
+use codespan_reporting::diagnostic::{self, Diagnostic as Report};
+use codespan_reporting::files::SimpleFile;
+use codespan_reporting::term::{self, termcolor::NoColor};
+use naga::WithSpan;
+use naga::front::glsl::ParseErrors;
+use naga::front::wgsl::ParseError;
+use naga::valid::ValidationError;
+
+use crate::prelude::Composed;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Error,
+}
+
+/// Position in the user shader, both line and column are 1-based,
+/// column is counted in characters
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Location {
+    pub line: usize,
+    pub column: usize,
+}
+
+impl Location {
+    /// Finds the location of a byte `offset` in `source`
+    pub fn find(source: &str, offset: usize) -> Self {
+        let offset = floor_char_boundary(source, offset);
+        let before = &source[..offset];
+        let line_start = before.rfind('\n').map(|i| i + 1).unwrap_or(0);
+        Location {
+            line: before.matches('\n').count() + 1,
+            column: before[line_start..].chars().count() + 1,
+        }
+    }
+}
+
+impl std::fmt::Display for Location {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}", self.line, self.column)
+    }
+}
+
+/// A highlighted part of the user shader
+#[derive(Debug, Clone)]
+pub struct Label {
+    /// Byte range in the user shader
+    pub span: Range<usize>,
+    pub start: Location,
+    pub end: Location,
+    pub message: String,
+}
+
+impl Label {
+    /// Splits the span into 0-based lines and byte column ranges,
+    /// an empty span covers a single character
+    pub fn lines(&self, source: &str) -> Vec<(usize, Range<usize>)> {
+        let start = floor_char_boundary(source, self.span.start);
+        let end = floor_char_boundary(source, self.span.end).max(start);
+
+        let mut lines = Vec::new();
+        let mut line_start = source[..start].rfind('\n').map(|i| i + 1).unwrap_or(0);
+        for line in self.start.line..=self.end.line {
+            let line_end = source[line_start..]
+                .find('\n')
+                .map(|i| line_start + i)
+                .unwrap_or(source.len());
+            let from = start.max(line_start) - line_start;
+            let to = end.min(line_end).saturating_sub(line_start);
+            lines.push((line - 1, from..to.max(from + 1)));
+
+            line_start = line_end + 1;
+            if line_start > source.len() {
+                break;
+            }
+        }
+        lines
+    }
+}
+
+/// A problem found in the shader, with spans
+/// pointing into the user source, not the composed one
+#[derive(Debug, Clone)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub message: String,
+    /// The first label is the primary one
+    pub labels: Vec<Label>,
+    pub notes: Vec<String>,
+}
+
+impl Diagnostic {
+    /// A diagnostic without a location in the source
+    pub fn error(message: impl Into<String>) -> Self {
+        Diagnostic {
+            severity: Severity::Error,
+            message: message.into(),
+            labels: Vec::new(),
+            notes: Vec::new(),
+        }
+    }
+
+    pub fn from_parse_error(error: &ParseError, shader: &str, composed: &Composed) -> Self {
+        let mut diagnostic = Diagnostic::error(error.message());
+        for (span, message) in error.labels() {
+            diagnostic.push_label(span, message, shader, composed);
+        }
+        diagnostic
+    }
+
+    /// The first error is the message, every error gets a label
+    pub fn from_glsl_errors(errors: &ParseErrors, shader: &str, composed: &Composed) -> Self {
+        let message = errors
+            .errors
+            .first()
+            .map(|error| error.kind.to_string())
+            .unwrap_or_else(|| "Invalid GLSL".to_owned());
+        let mut diagnostic = Diagnostic::error(message);
+        for error in &errors.errors {
+            diagnostic.push_label(error.meta, &error.kind.to_string(), shader, composed);
+        }
+        diagnostic
+    }
+
+    pub fn from_validation_error(
+        error: &WithSpan<ValidationError>,
+        shader: &str,
+        composed: &Composed,
+    ) -> Self {
+        let mut diagnostic = Diagnostic::error(error.as_inner().to_string());
+        for (span, message) in error.spans() {
+            diagnostic.push_label(*span, message, shader, composed);
+        }
+
+        let mut source: &dyn std::error::Error = error.as_inner();
+        while let Some(next) = source.source() {
+            diagnostic.notes.push(next.to_string());
+            source = next;
+        }
+        diagnostic
+    }
+
+    fn push_label(&mut self, span: naga::Span, message: &str, shader: &str, composed: &Composed) {
+        if !span.is_defined() {
+            return;
+        }
+        let Some(span) = composed.user_span(span) else {
+            self.notes
+                .push(format!("in the built-in declarations: {message}"));
+            return;
+        };
+        self.labels.push(Label {
+            start: Location::find(shader, span.start),
+            end: Location::find(shader, span.end),
+            span,
+            message: message.to_owned(),
+        });
+    }
+
+    /// Location of the primary label
+    pub fn location(&self) -> Option<Location> {
+        self.labels.first().map(|label| label.start)
+    }
+
+    /// Renders the diagnostic with source snippets, the same way
+    /// naga does in `emit_to_string`
+    pub fn emit_to_string(&self, shader: &str, path: &str) -> String {
+        let report = match self.severity {
+            Severity::Error => Report::error(),
+        }
+        .with_message(&self.message)
+        .with_labels(
+            self.labels
+                .iter()
+                .enumerate()
+                .map(|(index, label)| {
+                    let style = if index == 0 {
+                        diagnostic::LabelStyle::Primary
+                    } else {
+                        diagnostic::LabelStyle::Secondary
+                    };
+                    diagnostic::Label::new(style, (), label.span.clone())
+                        .with_message(&label.message)
+                })
+                .collect(),
+        )
+        .with_notes(self.notes.clone());
+
+        let file = SimpleFile::new(path, shader);
+        let mut writer = NoColor::new(Vec::new());
+        if let Err(e) = term::emit(&mut writer, &term::Config::default(), &file, &report) {
+            return format!("{self}\n(error rendering the source snippet: {e})");
+        }
+        String::from_utf8_lossy(&writer.into_inner()).into_owned()
+    }
+}
+
+impl std::fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.location() {
+            Some(location) => write!(f, "{location}: {}", self.message),
+            None => write!(f, "{}", self.message),
+        }
+    }
+}
+
+fn floor_char_boundary(source: &str, offset: usize) -> usize {
+    let mut offset = offset.min(source.len());
+    while !source.is_char_boundary(offset) {
+        offset -= 1;
+    }
+    offset
+}
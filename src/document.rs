@@ -0,0 +1,271 @@
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use iced_wgpu::Renderer;
+use iced_widget::text_editor;
+
+use crate::SHADER_TEMPLATE;
+use crate::diagnostic::Diagnostic;
+use crate::entry_points::{EntryPoints, Selection};
+use crate::language::{Language, Shader};
+use crate::scene::graph::Pass;
+use crate::validator::Validation;
+
+static NEXT_ID: AtomicU64 = AtomicU64::new(0);
+
+/// A shader opened in an editor tab
+pub struct Document {
+    /// Unique for the lifetime of the app, unlike the tab index
+    pub id: u64,
+    /// Incremented on every edit
+    pub revision: u64,
+    pub content: text_editor::Content<Renderer>,
+    pub path: Option<PathBuf>,
+    /// File name suggested when an unsaved document is saved
+    pub name: Option<String>,
+    /// Picked by the extension of the file
+    pub language: Language,
+    /// Module of a SPIR-V document, the content is its read-only disassembly
+    binary: Vec<u8>,
+    pub dirty: bool,
+    /// Pass of the render graph the shader is applied to
+    pub pass: Pass,
+    /// Entry points found by the last successful validation
+    pub entry_points: EntryPoints,
+    /// Entry points to render with
+    pub selection: Selection,
+    /// Result of the background validation of the current revision, if it passed
+    pub validation: Option<Arc<Validation>>,
+    /// Result of the last validation
+    pub diagnostic: Option<Diagnostic>,
+    /// `diagnostic` rendered with source snippets
+    pub report: String,
+    /// Lines and byte column ranges of the `diagnostic` labels
+    pub error_lines: Vec<(usize, Range<usize>)>,
+    /// Line numbers, built again only when the lines or the errors change
+    pub gutter: Vec<GutterBlock>,
+    /// Length of the longest line in characters, for the width of the editor
+    pub longest_line: usize,
+}
+
+/// Part of the line number gutter
+pub enum GutterBlock {
+    /// Numbers of consecutive lines without errors, one per line
+    Lines(String),
+    /// 1-based number of a line with an error and the message shown on hover
+    Error(usize, String),
+}
+
+impl Document {
+    pub fn untitled() -> Self {
+        let mut document = Document {
+            id: NEXT_ID.fetch_add(1, Ordering::Relaxed),
+            revision: 0,
+            content: text_editor::Content::with_text(SHADER_TEMPLATE),
+            path: None,
+            name: None,
+            language: Language::Wgsl,
+            binary: Vec::new(),
+            dirty: false,
+            pass: Pass::Image,
+            entry_points: EntryPoints::default(),
+            selection: Selection::default(),
+            validation: None,
+            diagnostic: None,
+            report: String::new(),
+            error_lines: Vec::new(),
+            gutter: Vec::new(),
+            longest_line: 0,
+        };
+        document.update_lines();
+        document
+    }
+
+    /// Unsaved GLSL fragment shader, like a pass of an imported Shadertoy shader
+    pub fn imported(name: String, text: &str) -> Self {
+        let mut document = Document::untitled();
+        document.content = text_editor::Content::with_text(text);
+        document.update_lines();
+        document.name = Some(name);
+        document.language = Language::Glsl(naga::ShaderStage::Fragment);
+        document.dirty = true;
+        document
+    }
+
+    pub fn open(path: &Path) -> Result<Self, String> {
+        let shader = Shader::read(path)?;
+        let mut document = Document {
+            id: NEXT_ID.fetch_add(1, Ordering::Relaxed),
+            revision: 0,
+            content: text_editor::Content::with_text(&shader.text()),
+            path: Some(path.to_path_buf()),
+            name: None,
+            language: shader.language(),
+            binary: match shader {
+                Shader::SpirV(bytes) => bytes,
+                _ => Vec::new(),
+            },
+            dirty: false,
+            pass: Pass::Image,
+            entry_points: EntryPoints::default(),
+            selection: Selection::default(),
+            validation: None,
+            diagnostic: None,
+            report: String::new(),
+            error_lines: Vec::new(),
+            gutter: Vec::new(),
+            longest_line: 0,
+        };
+        document.update_lines();
+        Ok(document)
+    }
+
+    /// The language changes with the extension, except for SPIR-V
+    /// which can't be converted to text and back
+    pub fn save(&mut self, path: PathBuf) -> Result<(), String> {
+        let language = Language::from_path(&path);
+        let contents = match (self.language, language) {
+            (Language::SpirV, Language::SpirV) => self.binary.clone(),
+            (Language::SpirV, _) => {
+                return Err("A SPIR-V module can be saved only as .spv".to_owned());
+            }
+            (_, Language::SpirV) => {
+                return Err("Export the SPIR-V translation to get a .spv file".to_owned());
+            }
+            _ => self.content.text().into_bytes(),
+        };
+        std::fs::write(&path, contents)
+            .map_err(|e| format!("Error writing {}: {e}", path.display()))?;
+        self.path = Some(path);
+        if language != self.language {
+            self.validation = None;
+        }
+        self.language = language;
+        self.dirty = false;
+        Ok(())
+    }
+
+    pub fn perform(&mut self, action: text_editor::Action) {
+        let is_edit = action.is_edit();
+        if is_edit && self.language == Language::SpirV {
+            return;
+        }
+        if is_edit {
+            self.dirty = true;
+            self.revision += 1;
+            self.validation = None;
+        }
+        self.content.perform(action);
+        if is_edit {
+            self.update_lines();
+        }
+    }
+
+    /// Source for the validator and the pipeline
+    pub fn shader(&self) -> Shader {
+        match self.language {
+            Language::SpirV => Shader::SpirV(self.binary.clone()),
+            language => Shader::new(language, self.content.text()),
+        }
+    }
+
+    pub fn set_diagnostic(&mut self, diagnostic: Option<Diagnostic>) {
+        self.report.clear();
+        self.error_lines.clear();
+        if let Some(diagnostic) = &diagnostic {
+            let text = self.content.text();
+            self.report = diagnostic.emit_to_string(&text, &self.title());
+            self.error_lines = diagnostic
+                .labels
+                .iter()
+                .flat_map(|label| label.lines(&text))
+                .collect();
+        }
+        self.diagnostic = diagnostic;
+        self.update_lines();
+    }
+
+    /// Rebuilds the gutter and measures the lines, the editor view
+    /// is drawn every frame and shouldn't do it
+    fn update_lines(&mut self) {
+        let mut gutter = Vec::new();
+        let mut numbers = String::new();
+        for line in 0..self.content.line_count() {
+            match self.error_message(line) {
+                Some(message) => {
+                    if !numbers.is_empty() {
+                        gutter.push(GutterBlock::Lines(std::mem::take(&mut numbers)));
+                    }
+                    gutter.push(GutterBlock::Error(line + 1, message));
+                }
+                None => {
+                    if !numbers.is_empty() {
+                        numbers.push('\n');
+                    }
+                    numbers.push_str(&(line + 1).to_string());
+                }
+            }
+        }
+        if !numbers.is_empty() {
+            gutter.push(GutterBlock::Lines(numbers));
+        }
+        self.gutter = gutter;
+        self.longest_line = self
+            .content
+            .lines()
+            .map(|line| line.text.chars().count())
+            .max()
+            .unwrap_or_default();
+    }
+
+    /// Characters before the byte `column` of the 0-based `line`,
+    /// the editor positions count bytes
+    pub fn char_column(&self, line: usize, column: usize) -> usize {
+        self.content
+            .line(line)
+            .and_then(|line| line.text.get(..column).map(|text| text.chars().count()))
+            .unwrap_or(column)
+    }
+
+    /// Moves the cursor to the start of the primary error label,
+    /// returns its line and byte column
+    pub fn go_to_error(&mut self) -> Option<(usize, usize)> {
+        let (line, columns) = self.error_lines.first()?.clone();
+        self.content.move_to(text_editor::Cursor {
+            position: text_editor::Position {
+                line,
+                column: columns.start,
+            },
+            selection: None,
+        });
+        Some((line, columns.start))
+    }
+
+    /// Message of the error label covering the 0-based `line`
+    fn error_message(&self, line: usize) -> Option<String> {
+        let diagnostic = self.diagnostic.as_ref()?;
+        let label = diagnostic
+            .labels
+            .iter()
+            .find(|label| (label.start.line..=label.end.line).contains(&(line + 1)))?;
+        if label.message.is_empty() {
+            Some(diagnostic.message.clone())
+        } else {
+            Some(format!("{}\n{}", diagnostic.message, label.message))
+        }
+    }
+
+    /// File name with a `*` suffix when there are unsaved changes
+    pub fn title(&self) -> String {
+        let name = self
+            .path
+            .as_deref()
+            .and_then(Path::file_name)
+            .map(|name| name.to_string_lossy().into_owned())
+            .or_else(|| self.name.clone())
+            .unwrap_or_else(|| "untitled".to_owned());
+        if self.dirty { format!("{name}*") } else { name }
+    }
+}
@@ -1,53 +1,216 @@
-use iced_wgpu::wgpu::{self, Device, SurfaceConfiguration};
+use std::path::Path;
 
-use crate::scene::{Scene, rectangle_pipeline};
+use iced_wgpu::wgpu::{self, Device, Queue, SurfaceConfiguration};
+use image::RgbaImage;
+
+use crate::SHADER_TEMPLATE;
+use crate::diagnostic::Diagnostic;
+use crate::entry_points::Selection;
+use crate::language::Shader;
+use crate::scene::channels::Channel;
+use crate::scene::compute::{ComputeData, Dispatch};
+use crate::scene::globals::Globals;
+use crate::scene::graph::{BUFFER_FORMAT, Pass};
+use crate::scene::offscreen::Offscreen;
+use crate::scene::{Scene, Targets, rectangle_pipeline};
+use crate::validator::{self, Validation};
 
 pub struct Editor {
     scene: Scene,
-    rectangle_pipeline_data: rectangle_pipeline::PipelineData,
+}
+
+/// Frames rendered offscreen with buffer passes and storage separate from the window,
+/// they start empty and keep the feedback between the frames
+pub struct Capture {
+    offscreen: Offscreen,
+    targets: Targets,
 }
 
 impl Editor {
-    pub fn init(config: &SurfaceConfiguration, device: &Device) -> Self {
-        let rectangle_pipeline_data = rectangle_pipeline::PipelineData::new(device, config);
-        let scene = Scene::new(device);
-        Editor {
-            scene,
-            rectangle_pipeline_data,
-        }
-    }
-    pub fn render(&self, view: &wgpu::TextureView, encoder: &mut wgpu::CommandEncoder) {
-        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
-            label: None,
-            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-                view,
-                depth_slice: None,
-                resolve_target: None,
-                ops: wgpu::Operations {
-                    load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
-                    store: wgpu::StoreOp::Store,
-                },
-            })],
-            depth_stencil_attachment: None,
-            timestamp_writes: None,
-            occlusion_query_set: None,
+    /// Renders the shader at `path` in the image pass, or the template if there's none
+    /// or it fails. Also returns the validation of the shader at `path`, the error is
+    /// for the user to see.
+    pub fn init(
+        config: &SurfaceConfiguration,
+        device: &Device,
+        queue: &Queue,
+        path: Option<&Path>,
+    ) -> (Self, Option<Result<Validation, Diagnostic>>) {
+        let mut scene = Scene::new(device, queue, config.width, config.height);
+        let layouts = &scene.bind_group_layouts();
+        let format = config.view_formats[0];
+        let result = path.map(|path| {
+            let shader = Shader::read(path).map_err(Diagnostic::error)?;
+            let validation = validator::validate(&shader)?;
+            // `vs_main` and `fs_main`, or the first ones of a file with several
+            let selection = validation.entry_points.resolve(&Selection::default());
+            rectangle_pipeline::PipelineData::new_with_validation(
+                device,
+                format,
+                layouts,
+                &shader,
+                &validation,
+                &selection,
+            )
+            .map(|pipeline_data| (pipeline_data, validation))
+            .map_err(Diagnostic::error)
         });
+        let (pipeline_data, result) = match result {
+            Some(Ok((pipeline_data, validation))) => (pipeline_data, Some(Ok(validation))),
+            result => (
+                template_pipeline(device, format, layouts),
+                result.map(|result| result.map(|(_, validation)| validation)),
+            ),
+        };
+        scene.set_pipeline(Pass::Image, Some(pipeline_data));
+        (Editor { scene }, result)
+    }
 
-        self.scene
-            .render(&self.rectangle_pipeline_data, &mut render_pass);
+    pub fn update_globals(&self, queue: &Queue, globals: &Globals) {
+        self.scene.globals.write(queue, globals);
     }
 
-    pub fn update_rectangle_shader(&mut self, device: &Device, config: &SurfaceConfiguration) {
-        self.rectangle_pipeline_data = rectangle_pipeline::PipelineData::new(device, config);
+    pub fn update_params(&self, queue: &Queue, bytes: &[u8]) {
+        self.scene.globals.write_params(queue, bytes);
     }
 
-    pub fn update_rectangle_shader_with_text(
+    pub fn render(
         &mut self,
         device: &Device,
-        config: &SurfaceConfiguration,
-        text: &str,
+        view: &wgpu::TextureView,
+        encoder: &mut wgpu::CommandEncoder,
     ) {
-        self.rectangle_pipeline_data =
-            rectangle_pipeline::PipelineData::new_with_text(device, config, text);
+        self.scene.render(device, encoder, view);
+    }
+
+    pub fn resize(&mut self, device: &Device, width: u32, height: u32) {
+        self.scene.resize(device, width, height);
+    }
+
+    /// Renders a frame at the size of `globals.resolution`, independent of the window.
+    /// The buffer passes start empty, the ones of the window are kept.
+    pub fn export(
+        &mut self,
+        device: &Device,
+        queue: &Queue,
+        config: &SurfaceConfiguration,
+        globals: &Globals,
+    ) -> Result<RgbaImage, String> {
+        let [width, height] = globals.resolution.map(|size| size as u32);
+        let mut capture = self.start_capture(device, config, width, height)?;
+        self.capture(device, queue, &mut capture, globals)
     }
+
+    pub fn start_capture(
+        &self,
+        device: &Device,
+        config: &SurfaceConfiguration,
+        width: u32,
+        height: u32,
+    ) -> Result<Capture, String> {
+        Ok(Capture {
+            offscreen: Offscreen::new(device, config.view_formats[0], width, height)?,
+            targets: self.scene.create_targets(device, width, height),
+        })
+    }
+
+    /// Renders the next frame of the capture, `globals.resolution` should be its size
+    pub fn capture(
+        &mut self,
+        device: &Device,
+        queue: &Queue,
+        capture: &mut Capture,
+        globals: &Globals,
+    ) -> Result<RgbaImage, String> {
+        self.update_globals(queue, globals);
+        let mut encoder =
+            device.create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
+        self.scene.swap_targets(&mut capture.targets);
+        self.scene
+            .render(device, &mut encoder, capture.offscreen.view());
+        self.scene.swap_targets(&mut capture.targets);
+        queue.submit([encoder.finish()]);
+        capture.offscreen.read(device, queue)
+    }
+
+    /// Keeps the current pipeline of the pass if the new one can't be created
+    pub fn update_shader(
+        &mut self,
+        device: &Device,
+        config: &SurfaceConfiguration,
+        pass: Pass,
+        shader: &Shader,
+        validation: &Validation,
+        selection: &Selection,
+    ) -> Result<(), String> {
+        if pass == Pass::Compute {
+            let compute_data = ComputeData::new_with_validation(
+                device,
+                &self.scene.compute_bind_group_layouts(),
+                shader,
+                validation,
+            )?;
+            self.scene.set_compute(Some(compute_data));
+            return Ok(());
+        }
+        let format = if pass.is_buffer() {
+            BUFFER_FORMAT
+        } else {
+            config.view_formats[0]
+        };
+        let pipeline_data = rectangle_pipeline::PipelineData::new_with_validation(
+            device,
+            format,
+            &self.scene.bind_group_layouts(),
+            shader,
+            validation,
+            selection,
+        )?;
+        self.scene.set_pipeline(pass, Some(pipeline_data));
+        Ok(())
+    }
+
+    /// The image pass is never empty, it goes back to the template
+    pub fn remove_pass(&mut self, device: &Device, config: &SurfaceConfiguration, pass: Pass) {
+        match pass {
+            Pass::Image => {
+                let layouts = &self.scene.bind_group_layouts();
+                let pipeline_data = template_pipeline(device, config.view_formats[0], layouts);
+                self.scene.set_pipeline(Pass::Image, Some(pipeline_data));
+            }
+            Pass::Compute => self.scene.set_compute(None),
+            _ => self.scene.set_pipeline(pass, None),
+        }
+    }
+
+    pub fn set_dispatch(&mut self, dispatch: Dispatch) {
+        self.scene.set_dispatch(dispatch);
+    }
+
+    pub fn update_channel(
+        &mut self,
+        device: &Device,
+        queue: &Queue,
+        pass: Pass,
+        index: usize,
+        channel: &Channel,
+    ) -> Result<(), String> {
+        self.scene.set_channel(device, queue, pass, index, channel)
+    }
+}
+
+fn template_pipeline(
+    device: &Device,
+    format: wgpu::TextureFormat,
+    layouts: &[&wgpu::BindGroupLayout],
+) -> rectangle_pipeline::PipelineData {
+    let template = &Shader::Wgsl(SHADER_TEMPLATE.to_owned());
+    rectangle_pipeline::PipelineData::new_with_shader(
+        device,
+        format,
+        layouts,
+        template,
+        &Selection::default(),
+    )
+    .expect("Should create the template pipeline")
 }
@@ -0,0 +1,49 @@
+/// Entry points of a validated shader, in the declaration order
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct EntryPoints {
+    pub vertex: Vec<String>,
+    pub fragment: Vec<String>,
+    pub compute: Vec<String>,
+}
+
+impl EntryPoints {
+    pub fn from_module(module: &naga::Module) -> Self {
+        let mut entry_points = EntryPoints::default();
+        for entry_point in &module.entry_points {
+            let names = match entry_point.stage {
+                naga::ShaderStage::Vertex => &mut entry_points.vertex,
+                naga::ShaderStage::Fragment => &mut entry_points.fragment,
+                naga::ShaderStage::Compute => &mut entry_points.compute,
+                _ => continue,
+            };
+            names.push(entry_point.name.clone());
+        }
+        entry_points
+    }
+
+    /// Entry points to render with, the selected ones if the shader still has them
+    pub fn resolve(&self, selection: &Selection) -> Selection {
+        Selection {
+            vertex: pick(&self.vertex, selection.vertex.as_deref(), "vs_main"),
+            fragment: pick(&self.fragment, selection.fragment.as_deref(), "fs_main"),
+        }
+    }
+}
+
+/// Vertex and fragment entry points chosen by the user,
+/// `None` for the default ones
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Selection {
+    pub vertex: Option<String>,
+    pub fragment: Option<String>,
+}
+
+/// `selected` if present, otherwise `default` or the first one
+fn pick(names: &[String], selected: Option<&str>, default: &str) -> Option<String> {
+    [selected, Some(default)]
+        .into_iter()
+        .flatten()
+        .find(|name| names.iter().any(|candidate| candidate == name))
+        .map(str::to_owned)
+        .or_else(|| names.first().cloned())
+}
@@ -0,0 +1,269 @@
+use std::path::{Path, PathBuf};
+
+use iced_wgpu::Renderer;
+use iced_widget::{button, column, container, row, scrollable, space, text, text_input};
+use iced_winit::core::{Element, Length, Theme};
+
+use crate::language;
+use crate::scene::channels;
+use crate::shadertoy;
+
+/// What happens when a file is picked
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mode {
+    Open,
+    SaveAs,
+    /// Pick an image for the input channel
+    OpenImage(usize),
+    /// Pick a PNG file for the exported frame
+    ExportImage,
+    /// Pick an animated PNG file for the recording
+    Record,
+    /// Pick a file with the extension for the translated shader
+    ExportTranslation(&'static str),
+    /// Pick a Shadertoy JSON export or snippet
+    Import,
+}
+
+/// Result of the user interaction with [`FileBrowser`]
+#[derive(Debug)]
+pub enum Event {
+    Open(PathBuf),
+    SaveAs(PathBuf),
+    OpenImage(usize, PathBuf),
+    ExportImage(PathBuf),
+    Record(PathBuf),
+    ExportTranslation(PathBuf),
+    Import(PathBuf),
+    Cancel,
+}
+
+#[derive(Debug, Clone)]
+pub enum Message {
+    Navigate(PathBuf),
+    Up,
+    Pick(PathBuf),
+    FileNameChanged(String),
+    Confirm,
+    Cancel,
+}
+
+#[derive(Debug)]
+struct Entry {
+    path: PathBuf,
+    name: String,
+    is_dir: bool,
+}
+
+/// A minimal in-app file dialog,
+/// works without a native file picker or desktop portal
+#[derive(Debug)]
+pub struct FileBrowser {
+    mode: Mode,
+    directory: PathBuf,
+    entries: Vec<Entry>,
+    file_name: String,
+    error: Option<String>,
+}
+
+impl FileBrowser {
+    /// Opens the browser in the directory of `current`,
+    /// or in the working directory if there is no current file
+    pub fn new(mode: Mode, current: Option<&Path>) -> Self {
+        let directory = current
+            .and_then(Path::parent)
+            .filter(|parent| !parent.as_os_str().is_empty())
+            .map(Path::to_path_buf)
+            .unwrap_or_else(|| PathBuf::from("."));
+        let file_name = current
+            .and_then(Path::file_name)
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+
+        let mut browser = FileBrowser {
+            mode,
+            directory,
+            entries: Vec::new(),
+            file_name,
+            error: None,
+        };
+        browser.read_directory();
+        browser
+    }
+
+    pub fn update(&mut self, message: Message) -> Option<Event> {
+        match message {
+            Message::Navigate(directory) => {
+                self.directory = directory;
+                self.read_directory();
+                None
+            }
+            Message::Up => {
+                let directory = std::fs::canonicalize(&self.directory)
+                    .ok()
+                    .and_then(|directory| directory.parent().map(Path::to_path_buf));
+                if let Some(directory) = directory {
+                    self.directory = directory;
+                    self.read_directory();
+                }
+                None
+            }
+            Message::Pick(path) => match self.mode {
+                Mode::Open => Some(Event::Open(path)),
+                Mode::OpenImage(index) => Some(Event::OpenImage(index, path)),
+                Mode::Import => Some(Event::Import(path)),
+                Mode::SaveAs | Mode::ExportImage | Mode::Record | Mode::ExportTranslation(_) => {
+                    if let Some(name) = path.file_name() {
+                        self.file_name = name.to_string_lossy().into_owned();
+                    }
+                    None
+                }
+            },
+            Message::FileNameChanged(file_name) => {
+                self.file_name = file_name;
+                None
+            }
+            Message::Confirm => {
+                if self.file_name.trim().is_empty() {
+                    self.error = Some("File name is empty".to_owned());
+                    return None;
+                }
+                let mut path = self.directory.join(self.file_name.trim());
+                // exported files are written in a single format, whatever the name says
+                let extension = match self.mode {
+                    Mode::ExportImage | Mode::Record => Some("png"),
+                    Mode::ExportTranslation(extension) => Some(extension),
+                    _ => None,
+                };
+                if let Some(extension) = extension
+                    && !has_extension(&path, extension)
+                {
+                    let mut name = path.into_os_string();
+                    name.push(".");
+                    name.push(extension);
+                    path = PathBuf::from(name);
+                }
+                match self.mode {
+                    Mode::Open => Some(Event::Open(path)),
+                    Mode::SaveAs => Some(Event::SaveAs(path)),
+                    Mode::OpenImage(index) => Some(Event::OpenImage(index, path)),
+                    Mode::ExportImage => Some(Event::ExportImage(path)),
+                    Mode::Record => Some(Event::Record(path)),
+                    Mode::ExportTranslation(_) => Some(Event::ExportTranslation(path)),
+                    Mode::Import => Some(Event::Import(path)),
+                }
+            }
+            Message::Cancel => Some(Event::Cancel),
+        }
+    }
+
+    fn read_directory(&mut self) {
+        self.entries.clear();
+        self.error = None;
+
+        let read_dir = match std::fs::read_dir(&self.directory) {
+            Ok(read_dir) => read_dir,
+            Err(e) => {
+                self.error = Some(format!("Error reading {}: {e}", self.directory.display()));
+                return;
+            }
+        };
+
+        for dir_entry in read_dir.flatten() {
+            let path = dir_entry.path();
+            let is_dir = path.is_dir();
+            let is_listed = match self.mode {
+                Mode::Open | Mode::SaveAs => language::is_shader(&path),
+                Mode::OpenImage(_) => channels::has_image_extension(&path),
+                Mode::ExportImage | Mode::Record => has_extension(&path, "png"),
+                Mode::ExportTranslation(extension) => has_extension(&path, extension),
+                Mode::Import => shadertoy::is_importable(&path),
+            };
+            if !is_dir && !is_listed {
+                continue;
+            }
+            let name = dir_entry.file_name().to_string_lossy().into_owned();
+            self.entries.push(Entry { path, name, is_dir });
+        }
+
+        self.entries
+            .sort_by(|a, b| b.is_dir.cmp(&a.is_dir).then_with(|| a.name.cmp(&b.name)));
+    }
+
+    pub fn view(&self) -> Element<'_, Message, Theme, Renderer> {
+        let title = match self.mode {
+            Mode::Open => "Open shader".to_owned(),
+            Mode::SaveAs => "Save shader as".to_owned(),
+            Mode::OpenImage(index) => format!("Open image for iChannel{index}"),
+            Mode::ExportImage => "Export frame as".to_owned(),
+            Mode::Record => "Record animation as".to_owned(),
+            Mode::ExportTranslation(_) => "Export translation as".to_owned(),
+            Mode::Import => "Import Shadertoy shader".to_owned(),
+        };
+
+        let header = row![
+            button("Up").on_press(Message::Up).style(button::secondary),
+            text(self.directory.display().to_string()),
+        ]
+        .spacing(4);
+
+        let entries = self.entries.iter().map(|entry| {
+            let (label, message) = if entry.is_dir {
+                (
+                    format!("{}/", entry.name),
+                    Message::Navigate(entry.path.clone()),
+                )
+            } else {
+                (entry.name.clone(), Message::Pick(entry.path.clone()))
+            };
+            button(text(label))
+                .on_press(message)
+                .width(Length::Fill)
+                .style(button::text)
+                .into()
+        });
+
+        let list = scrollable(column(entries)).height(300);
+
+        let mut column = column![text(title), header, list].spacing(4).padding(4);
+
+        if matches!(
+            self.mode,
+            Mode::SaveAs | Mode::ExportImage | Mode::Record | Mode::ExportTranslation(_)
+        ) {
+            column = column.push(
+                text_input("File name", &self.file_name)
+                    .on_input(Message::FileNameChanged)
+                    .on_submit(Message::Confirm),
+            );
+        }
+
+        if let Some(error) = &self.error {
+            column = column.push(text(error));
+        }
+
+        let confirm = match self.mode {
+            Mode::Open | Mode::OpenImage(_) => "Open",
+            Mode::SaveAs => "Save",
+            Mode::ExportImage | Mode::ExportTranslation(_) => "Export",
+            Mode::Record => "Record",
+            Mode::Import => "Import",
+        };
+        column = column.push(row![
+            space::horizontal(),
+            button("Cancel")
+                .on_press(Message::Cancel)
+                .style(button::secondary),
+            button(confirm)
+                .on_press(Message::Confirm)
+                .style(button::secondary),
+        ]);
+
+        container(column).into()
+    }
+}
+
+fn has_extension(path: &Path, expected: &str) -> bool {
+    path.extension()
+        .is_some_and(|extension| extension.eq_ignore_ascii_case(expected))
+}
@@ -0,0 +1,141 @@
+use std::path::Path;
+
+use image::{Rgba, RgbaImage};
+
+use crate::batch;
+use crate::cli::GoldenArgs;
+use crate::entry_points::Selection;
+use crate::headless::{self, Headless};
+use crate::scene::globals::Globals;
+
+/// Outcome of comparing a shader with its reference image
+enum Outcome {
+    Passed,
+    /// Written as the new reference
+    Blessed,
+    Failed(String),
+}
+
+/// Renders every shader of the `golden` subcommand and compares it with the reference image,
+/// returns `false` if any of them fails
+pub fn run(args: &GoldenArgs) -> Result<bool, String> {
+    let mut shaders = Vec::new();
+    batch::collect_shaders(&args.shaders, &mut shaders)?;
+
+    // fixed values, so the frames don't depend on the clock or the input
+    let mut globals = Globals::default();
+    globals.resolution = [args.width as f32, args.height as f32];
+    globals.time = args.time;
+    globals.frame = args.frame;
+
+    // the CPU interpreter doesn't need a device
+    let headless = if args.cpu {
+        None
+    } else {
+        Some(Headless::new()?)
+    };
+    let mut failed = 0;
+    for shader in &shaders {
+        let relative = shader.strip_prefix(&args.shaders).unwrap_or(shader);
+        let outcome = check(headless.as_ref(), args, shader, relative, &globals);
+        match outcome {
+            Outcome::Passed => println!("ok      {}", relative.display()),
+            Outcome::Blessed => println!("blessed {}", relative.display()),
+            Outcome::Failed(reason) => {
+                failed += 1;
+                println!("FAILED  {}: {reason}", relative.display());
+            }
+        }
+    }
+    println!("Checked {} shaders, {failed} failed", shaders.len());
+    Ok(failed == 0)
+}
+
+fn check(
+    headless: Option<&Headless>,
+    args: &GoldenArgs,
+    shader: &Path,
+    relative: &Path,
+    globals: &Globals,
+) -> Outcome {
+    let selection = Selection::default();
+    let rendered = match headless {
+        Some(headless) => headless.render(shader, &selection, globals),
+        None => headless::render_cpu(shader, &selection, globals),
+    };
+    let rendered = match rendered {
+        Ok(rendered) => rendered,
+        Err(e) => return Outcome::Failed(e),
+    };
+    let reference_path = args.references.join(relative).with_extension("png");
+
+    if args.bless {
+        return match save(&rendered, &reference_path) {
+            Ok(()) => Outcome::Blessed,
+            Err(e) => Outcome::Failed(e),
+        };
+    }
+
+    let reference = match image::open(&reference_path) {
+        Ok(reference) => reference.into_rgba8(),
+        Err(e) => {
+            return Outcome::Failed(format!(
+                "Error reading {}: {e}, run with --bless to create it",
+                reference_path.display()
+            ));
+        }
+    };
+    if reference.dimensions() != rendered.dimensions() {
+        let (width, height) = reference.dimensions();
+        return Outcome::Failed(format!(
+            "The reference is {width}x{height}, rendered {}x{}",
+            args.width, args.height
+        ));
+    }
+
+    let (different, diff) = compare(&rendered, &reference, args.tolerance);
+    if different == 0 {
+        return Outcome::Passed;
+    }
+    let diff_path = args.diffs.join(relative).with_extension("png");
+    let mut reason = format!("{different} pixels differ by more than {}", args.tolerance);
+    match save(&diff, &diff_path) {
+        Ok(()) => reason.push_str(&format!(", see {}", diff_path.display())),
+        Err(e) => reason.push_str(&format!(", {e}")),
+    }
+    Outcome::Failed(reason)
+}
+
+/// Counts the pixels with any channel differing by more than `tolerance`,
+/// the diff image marks them red over the dimmed reference
+fn compare(rendered: &RgbaImage, reference: &RgbaImage, tolerance: u8) -> (usize, RgbaImage) {
+    let mut different = 0;
+    let diff = RgbaImage::from_fn(reference.width(), reference.height(), |x, y| {
+        let expected = reference.get_pixel(x, y);
+        let actual = rendered.get_pixel(x, y);
+        let exceeds = expected
+            .0
+            .iter()
+            .zip(actual.0)
+            .any(|(expected, actual)| expected.abs_diff(actual) > tolerance);
+        if exceeds {
+            different += 1;
+            Rgba([255, 0, 0, 255])
+        } else {
+            let [r, g, b, _] = expected.0;
+            let dimmed = ((u16::from(r) + u16::from(g) + u16::from(b)) / 12) as u8;
+            Rgba([dimmed, dimmed, dimmed, 255])
+        }
+    });
+    (different, diff)
+}
+
+fn save(image: &RgbaImage, path: &Path) -> Result<(), String> {
+    if let Some(directory) = path.parent() {
+        std::fs::create_dir_all(directory)
+            .map_err(|e| format!("Error creating {}: {e}", directory.display()))?;
+    }
+    image
+        .save_with_format(path, image::ImageFormat::Png)
+        .map_err(|e| format!("Error writing {}: {e}", path.display()))
+}
@@ -0,0 +1,164 @@
+use std::path::Path;
+
+use iced_wgpu::wgpu;
+use image::RgbaImage;
+
+use crate::cli::RenderArgs;
+use crate::clock::STEP;
+use crate::editor::Editor;
+use crate::entry_points::Selection;
+use crate::interpreter;
+use crate::language::Shader;
+use crate::params::Params;
+use crate::scene::globals::Globals;
+use crate::scene::graph::Pass;
+use crate::validator::{self, Validation};
+
+/// Format of the image pass without a window
+const FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba8UnormSrgb;
+
+/// GPU device without a window or a surface, renders shaders offscreen
+pub struct Headless {
+    device: wgpu::Device,
+    queue: wgpu::Queue,
+}
+
+impl Headless {
+    /// Uses the software adapter if there is no other one
+    pub fn new() -> Result<Self, String> {
+        let instance = wgpu::Instance::new(&wgpu::InstanceDescriptor::from_env_or_default());
+        futures::executor::block_on(async {
+            let adapter =
+                match wgpu::util::initialize_adapter_from_env_or_default(&instance, None).await {
+                    Ok(adapter) => adapter,
+                    Err(_) => instance
+                        .request_adapter(&wgpu::RequestAdapterOptions {
+                            force_fallback_adapter: true,
+                            ..Default::default()
+                        })
+                        .await
+                        .map_err(|e| format!("Adapter not found: {e}"))?,
+                };
+
+            let adapter_info = adapter.get_info();
+            println!("Using {} ({:?})", adapter_info.name, adapter_info.backend);
+
+            let (device, queue) = adapter
+                .request_device(&wgpu::DeviceDescriptor {
+                    label: None,
+                    required_features: adapter.features() & wgpu::Features::default(),
+                    required_limits: wgpu::Limits::default().using_resolution(adapter.limits()),
+                    memory_hints: wgpu::MemoryHints::MemoryUsage,
+                    trace: wgpu::Trace::Off,
+                    experimental_features: wgpu::ExperimentalFeatures::disabled(),
+                })
+                .await
+                .map_err(|e| format!("Device not found: {e}"))?;
+            Ok(Headless { device, queue })
+        })
+    }
+
+    /// Renders the shader file into the image pass at the size of `globals.resolution`,
+    /// validation errors are reported with source snippets
+    pub fn render(
+        &self,
+        path: &Path,
+        selection: &Selection,
+        globals: &Globals,
+    ) -> Result<RgbaImage, String> {
+        let (shader, validation) = read_shader(path, selection)?;
+        let params = Params::new(validation.params.clone()).bytes();
+
+        let [width, height] = globals.resolution.map(|size| size as u32);
+        let config = wgpu::SurfaceConfiguration {
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+            format: FORMAT,
+            width,
+            height,
+            present_mode: wgpu::PresentMode::AutoVsync,
+            alpha_mode: wgpu::CompositeAlphaMode::Auto,
+            view_formats: vec![FORMAT],
+            desired_maximum_frame_latency: 2,
+        };
+
+        // the default handler of the errors outside of a scope panics
+        self.device.push_error_scope(wgpu::ErrorFilter::Validation);
+        let (mut editor, _) = Editor::init(&config, &self.device, &self.queue, None);
+        let result = editor
+            .update_shader(
+                &self.device,
+                &config,
+                Pass::Image,
+                &shader,
+                &validation,
+                &validation.entry_points.resolve(selection),
+            )
+            .and_then(|()| {
+                editor.update_params(&self.queue, &params);
+                editor.export(&self.device, &self.queue, &config, globals)
+            });
+        if let Some(error) = futures::executor::block_on(self.device.pop_error_scope()) {
+            return Err(error.to_string());
+        }
+        result
+    }
+}
+
+/// Renders the shader file with the CPU interpreter, see [`Headless::render`]
+pub fn render_cpu(
+    path: &Path,
+    selection: &Selection,
+    globals: &Globals,
+) -> Result<RgbaImage, String> {
+    let (shader, validation) = read_shader(path, selection)?;
+    let params = Params::new(validation.params).bytes();
+    interpreter::render(&shader, selection, globals, &params)
+        .map_err(|e| format!("{}: {e}", path.display()))
+}
+
+/// Reads and validates the shader, checks that the selected entry points exist
+fn read_shader(path: &Path, selection: &Selection) -> Result<(Shader, Validation), String> {
+    let shader = Shader::read(path)?;
+    let validation = validator::validate(&shader).map_err(|diagnostic| {
+        diagnostic.emit_to_string(&shader.text(), &path.display().to_string())
+    })?;
+
+    let requested = [
+        (&selection.vertex, &validation.entry_points.vertex),
+        (&selection.fragment, &validation.entry_points.fragment),
+    ];
+    for (name, names) in requested {
+        if let Some(name) = name
+            && !names.contains(name)
+        {
+            return Err(format!("{} has no entry point {name}", path.display()));
+        }
+    }
+    Ok((shader, validation))
+}
+
+/// Renders a single frame for the `render` subcommand
+pub fn run(args: &RenderArgs) -> Result<(), String> {
+    let mut globals = Globals::default();
+    globals.resolution = [args.width as f32, args.height as f32];
+    globals.time = args.time;
+    globals.time_delta = STEP;
+    globals.frame = args.frame;
+    globals.set_date_now();
+
+    let selection = Selection {
+        vertex: args.vertex.clone(),
+        fragment: args.fragment.clone(),
+    };
+
+    let image = if args.cpu {
+        render_cpu(&args.shader, &selection, &globals)?
+    } else {
+        Headless::new()?.render(&args.shader, &selection, &globals)?
+    };
+    image
+        .save_with_format(&args.output, image::ImageFormat::Png)
+        .map_err(|e| format!("Error writing {}: {e}", args.output.display()))?;
+    println!("Rendered {}", args.output.display());
+    Ok(())
+}
@@ -1,5 +1,5 @@
 // A copy of https://github.com/iced-rs/iced/blob/master/highlighter/src/lib.rs
-// to load custom SyntaxSet
+// to load custom SyntaxSet and mark error ranges
 
 use std::ops::Range;
 use std::sync::LazyLock;
@@ -14,21 +14,33 @@ use syntect::parsing::{
     ParseState, ScopeStack, ScopeStackOp, SyntaxReference, SyntaxSet, SyntaxSetBuilder,
 };
 
-const SYNTAX_SOURCE: &str = include_str!("../wgsl.sublime-syntax");
+/// Syntax definitions of the languages accepted by the editor,
+/// SPIR-V is highlighted in the disassembly
+const SYNTAX_SOURCES: [&str; 3] = [
+    include_str!("../wgsl.sublime-syntax"),
+    include_str!("../glsl.sublime-syntax"),
+    include_str!("../spirv.sublime-syntax"),
+];
 
 static SYNTAX_SET: LazyLock<SyntaxSet> = LazyLock::new(make_syntax_set);
 static THEMES: LazyLock<ThemeSet> = LazyLock::new(ThemeSet::load_defaults);
 
 fn make_syntax_set() -> SyntaxSet {
-    let definition = SyntaxDefinition::load_from_str(SYNTAX_SOURCE, true, None)
-        .expect("Should load syntax definition");
     let mut builder = SyntaxSetBuilder::new();
-    builder.add(definition);
+    builder.add_plain_text_syntax();
+    for source in SYNTAX_SOURCES {
+        let definition = SyntaxDefinition::load_from_str(source, true, None)
+            .expect("Should load syntax definition");
+        builder.add(definition);
+    }
     builder.build()
 }
 
 const LINES_PER_SNAPSHOT: usize = 50;
 
+/// Color of the text inside error ranges
+const ERROR_COLOR: Color = Color::from_rgb8(0xdc, 0x32, 0x2f);
+
 /// A syntax highlighter.
 #[derive(Debug)]
 pub struct Highlighter {
@@ -36,6 +48,7 @@ pub struct Highlighter {
     highlighter: highlighting::Highlighter<'static>,
     caches: Vec<(ParseState, ScopeStack)>,
     current_line: usize,
+    errors: Vec<(usize, Range<usize>)>,
 }
 
 impl highlighter::Highlighter for Highlighter {
@@ -45,9 +58,7 @@ impl highlighter::Highlighter for Highlighter {
     type Iterator<'a> = Box<dyn Iterator<Item = (Range<usize>, Self::Highlight)> + 'a>;
 
     fn new(settings: &Self::Settings) -> Self {
-        let syntax = SYNTAX_SET
-            .find_syntax_by_extension("wgsl")
-            .unwrap_or_else(|| SYNTAX_SET.find_syntax_plain_text());
+        let syntax = find_syntax(&settings.token);
 
         let highlighter = highlighting::Highlighter::new(&THEMES.themes[settings.theme.key()]);
 
@@ -59,11 +70,14 @@ impl highlighter::Highlighter for Highlighter {
             highlighter,
             caches: vec![(parser, stack)],
             current_line: 0,
+            errors: settings.errors.clone(),
         }
     }
 
     fn update(&mut self, new_settings: &Self::Settings) {
+        self.syntax = find_syntax(&new_settings.token);
         self.highlighter = highlighting::Highlighter::new(&THEMES.themes[new_settings.theme.key()]);
+        self.errors = new_settings.errors.clone();
         self.change_line(0);
     }
 
@@ -94,13 +108,32 @@ impl highlighter::Highlighter for Highlighter {
             self.caches.push((parser.clone(), stack.clone()));
         }
 
+        let line_index = self.current_line;
         self.current_line += 1;
 
         let (parser, stack) = self.caches.last_mut().expect("Caches must not be empty");
 
         let ops = parser.parse_line(line, &SYNTAX_SET).unwrap_or_default();
 
-        Box::new(scope_iterator(ops, line, stack, &self.highlighter))
+        let highlights = scope_iterator(ops, line, stack, &self.highlighter);
+
+        let errors: Vec<Range<usize>> = self
+            .errors
+            .iter()
+            .filter(|(error_line, _)| *error_line == line_index)
+            .map(|(_, range)| range.clone())
+            .collect();
+
+        if errors.is_empty() {
+            Box::new(highlights)
+        } else {
+            Box::new(
+                highlights
+                    .flat_map(move |(range, highlight)| split_errors(range, highlight, &errors))
+                    .collect::<Vec<_>>()
+                    .into_iter(),
+            )
+        }
     }
 
     fn current_line(&self) -> usize {
@@ -108,6 +141,12 @@ impl highlighter::Highlighter for Highlighter {
     }
 }
 
+fn find_syntax(token: &str) -> &'static SyntaxReference {
+    SYNTAX_SET
+        .find_syntax_by_token(token)
+        .unwrap_or_else(|| SYNTAX_SET.find_syntax_plain_text())
+}
+
 fn scope_iterator<'a>(
     ops: Vec<(usize, ScopeStackOp)>,
     line: &str,
@@ -128,12 +167,42 @@ fn scope_iterator<'a>(
         } else {
             Some((
                 range,
-                Highlight(highlighter.style_mod_for_stack(&stack.scopes)),
+                Highlight(highlighter.style_mod_for_stack(&stack.scopes), false),
             ))
         }
     })
 }
 
+/// Splits a highlighted `range` at the boundaries of the `errors`,
+/// marking the parts inside of them
+fn split_errors(
+    range: Range<usize>,
+    highlight: Highlight,
+    errors: &[Range<usize>],
+) -> Vec<(Range<usize>, Highlight)> {
+    let mut points = vec![range.start, range.end];
+    for error in errors {
+        for point in [error.start, error.end] {
+            if range.start < point && point < range.end {
+                points.push(point);
+            }
+        }
+    }
+    points.sort_unstable();
+    points.dedup();
+
+    points
+        .windows(2)
+        .map(|window| {
+            let part = window[0]..window[1];
+            let error = errors
+                .iter()
+                .any(|error| error.start <= part.start && part.end <= error.end);
+            (part, Highlight(highlight.0, error))
+        })
+        .collect()
+}
+
 /// The settings of a [`Highlighter`].
 #[derive(Debug, Clone, PartialEq)]
 pub struct Settings {
@@ -146,17 +215,26 @@ pub struct Settings {
     /// The [`Highlighter`] will use the token to automatically determine
     /// the grammar to use for highlighting.
     pub token: String,
+    /// Ranges to mark as errors, as a 0-based line
+    /// and a range of byte columns in that line.
+    pub errors: Vec<(usize, Range<usize>)>,
 }
 
 /// A highlight produced by a [`Highlighter`].
+///
+/// The second field is `true` inside of an error range.
 #[derive(Debug)]
-pub struct Highlight(highlighting::StyleModifier);
+pub struct Highlight(highlighting::StyleModifier, bool);
 
 impl Highlight {
     /// Returns the color of this [`Highlight`].
     ///
     /// If `None`, the original text color should be unchanged.
     pub fn color(&self) -> Option<Color> {
+        if self.1 {
+            return Some(ERROR_COLOR);
+        }
+
         self.0
             .foreground
             .map(|color| Color::from_rgba8(color.r, color.g, color.b, color.a as f32 / 255.0))
@@ -166,6 +244,13 @@ impl Highlight {
     ///
     /// If `None`, the original font should be unchanged.
     pub fn font(&self) -> Option<Font> {
+        if self.1 {
+            return Some(Font {
+                weight: font::Weight::Bold,
+                ..Font::MONOSPACE
+            });
+        }
+
         self.0.font_style.and_then(|style| {
             let bold = style.contains(highlighting::FontStyle::BOLD);
             let italic = style.contains(highlighting::FontStyle::ITALIC);
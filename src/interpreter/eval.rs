@@ -0,0 +1,595 @@
+use naga::{
+    AddressSpace, Block, Expression, Function, GlobalVariable, Handle, Module, RelationalFunction,
+    Statement, SwitchValue, TypeInner,
+};
+
+use super::math;
+use super::value::{Pointer, Root, Value};
+use crate::params;
+use crate::scene::globals::Globals;
+
+type Result<T> = std::result::Result<T, String>;
+
+/// Iterations of a single loop before the shader is considered stuck
+const MAX_ITERATIONS: usize = 1 << 20;
+
+/// How a block of statements ended
+enum Flow {
+    Next,
+    Break,
+    Continue,
+    Return(Option<Value>),
+    Kill,
+}
+
+/// Arena of an expression handle
+#[derive(Clone, Copy)]
+enum Scope {
+    /// Constant expressions of the module
+    Global,
+    /// Expressions of the function on top of the call stack
+    Function,
+}
+
+struct Frame<'a> {
+    function: &'a Function,
+    arguments: Vec<Value>,
+    locals: Vec<Value>,
+    /// Values of the evaluated expressions, reset by every `Emit`
+    expressions: Vec<Option<Value>>,
+}
+
+/// Runs the functions of a validated module one invocation at a time
+pub struct Interpreter<'a> {
+    module: &'a Module,
+    uniforms: &'a Globals,
+    /// Content of the buffer of the parameters, see [`crate::params`]
+    params: &'a [u8],
+    /// Global variables, initialized on the first access of every invocation
+    variables: Vec<Option<Value>>,
+    constants: Vec<Option<Value>>,
+    frames: Vec<Frame<'a>>,
+}
+
+impl<'a> Interpreter<'a> {
+    pub fn new(module: &'a Module, uniforms: &'a Globals, params: &'a [u8]) -> Self {
+        Interpreter {
+            module,
+            uniforms,
+            params,
+            variables: vec![None; module.global_variables.len()],
+            constants: vec![None; module.global_expressions.len()],
+            frames: Vec::new(),
+        }
+    }
+
+    /// Runs an entry point, `None` if the invocation was discarded
+    pub fn run(&mut self, function: &'a Function, arguments: Vec<Value>) -> Result<Option<Value>> {
+        // private variables don't outlive the invocation
+        self.variables
+            .iter_mut()
+            .for_each(|variable| *variable = None);
+        self.frames.clear();
+        match self.call(function, arguments)? {
+            Flow::Return(value) => Ok(value),
+            _ => Ok(None),
+        }
+    }
+
+    /// Ends with [`Flow::Return`] or [`Flow::Kill`]
+    fn call(&mut self, function: &'a Function, arguments: Vec<Value>) -> Result<Flow> {
+        let locals = function
+            .local_variables
+            .iter()
+            .map(|(_, variable)| Value::zero(self.module, variable.ty))
+            .collect::<Result<_>>()?;
+        self.frames.push(Frame {
+            function,
+            arguments,
+            locals,
+            expressions: vec![None; function.expressions.len()],
+        });
+        for (handle, variable) in function.local_variables.iter() {
+            if let Some(init) = variable.init {
+                let value = self.operand(Scope::Function, init)?;
+                self.frame_mut().locals[handle.index()] = value;
+            }
+        }
+
+        let flow = self.block(&function.body)?;
+        self.frames.pop();
+        match flow {
+            Flow::Kill => Ok(Flow::Kill),
+            Flow::Return(value) => Ok(Flow::Return(value)),
+            // the end of a function without a result
+            _ => Ok(Flow::Return(None)),
+        }
+    }
+
+    fn frame(&self) -> &Frame<'a> {
+        self.frames
+            .last()
+            .expect("Should be called inside of a function")
+    }
+
+    fn frame_mut(&mut self) -> &mut Frame<'a> {
+        self.frames
+            .last_mut()
+            .expect("Should be called inside of a function")
+    }
+
+    fn block(&mut self, block: &'a Block) -> Result<Flow> {
+        for statement in block.iter() {
+            match self.statement(statement)? {
+                Flow::Next => (),
+                flow => return Ok(flow),
+            }
+        }
+        Ok(Flow::Next)
+    }
+
+    fn statement(&mut self, statement: &'a Statement) -> Result<Flow> {
+        match statement {
+            Statement::Emit(range) => {
+                for handle in range.clone() {
+                    let value = self.expression(Scope::Function, handle)?;
+                    self.frame_mut().expressions[handle.index()] = Some(value);
+                }
+            }
+            Statement::Block(block) => return self.block(block),
+            Statement::If {
+                condition,
+                accept,
+                reject,
+            } => {
+                let condition = self.operand(Scope::Function, *condition)?.as_bool()?;
+                return self.block(if condition { accept } else { reject });
+            }
+            Statement::Switch { selector, cases } => {
+                let selector = self.operand(Scope::Function, *selector)?;
+                let matches = |value: &SwitchValue| match (value, &selector) {
+                    (SwitchValue::I32(value), Value::I32(selector)) => value == selector,
+                    (SwitchValue::U32(value), Value::U32(selector)) => value == selector,
+                    _ => false,
+                };
+                let start = cases
+                    .iter()
+                    .position(|case| matches(&case.value))
+                    .or_else(|| {
+                        cases
+                            .iter()
+                            .position(|case| case.value == SwitchValue::Default)
+                    });
+                if let Some(start) = start {
+                    for case in &cases[start..] {
+                        match self.block(&case.body)? {
+                            Flow::Next if case.fall_through => continue,
+                            Flow::Next | Flow::Break => break,
+                            flow => return Ok(flow),
+                        }
+                    }
+                }
+            }
+            Statement::Loop {
+                body,
+                continuing,
+                break_if,
+            } => {
+                for _ in 0..MAX_ITERATIONS {
+                    match self.block(body)? {
+                        Flow::Next | Flow::Continue => (),
+                        Flow::Break => return Ok(Flow::Next),
+                        flow => return Ok(flow),
+                    }
+                    match self.block(continuing)? {
+                        Flow::Next => (),
+                        flow => return Ok(flow),
+                    }
+                    if let Some(condition) = break_if
+                        && self.operand(Scope::Function, *condition)?.as_bool()?
+                    {
+                        return Ok(Flow::Next);
+                    }
+                }
+                return Err(format!(
+                    "A loop ran for {MAX_ITERATIONS} iterations without finishing"
+                ));
+            }
+            Statement::Break => return Ok(Flow::Break),
+            Statement::Continue => return Ok(Flow::Continue),
+            Statement::Return { value } => {
+                let value = value
+                    .map(|value| self.operand(Scope::Function, value))
+                    .transpose()?;
+                return Ok(Flow::Return(value));
+            }
+            Statement::Kill => return Ok(Flow::Kill),
+            // invocations run one at a time, there is nothing to synchronize
+            Statement::ControlBarrier(_) | Statement::MemoryBarrier(_) => (),
+            Statement::Store { pointer, value } => {
+                let pointer = self.operand(Scope::Function, *pointer)?.into_pointer()?;
+                let value = self.operand(Scope::Function, *value)?;
+                *self.pointee(&pointer)? = value;
+            }
+            Statement::Call {
+                function,
+                arguments,
+                result,
+            } => {
+                let arguments = arguments
+                    .iter()
+                    .map(|&argument| self.operand(Scope::Function, argument))
+                    .collect::<Result<_>>()?;
+                let module = self.module;
+                match self.call(&module.functions[*function], arguments)? {
+                    Flow::Return(Some(value)) => {
+                        if let Some(result) = result {
+                            self.frame_mut().expressions[result.index()] = Some(value);
+                        }
+                    }
+                    Flow::Return(None) => (),
+                    // the callee discarded the invocation
+                    flow => return Ok(flow),
+                }
+            }
+            other => {
+                let name = variant_name(other);
+                return Err(format!("{name} is not supported by the CPU renderer"));
+            }
+        }
+        Ok(Flow::Next)
+    }
+
+    /// Value of an evaluated expression, the expressions outside of `Emit` are evaluated on demand
+    fn operand(&mut self, scope: Scope, handle: Handle<Expression>) -> Result<Value> {
+        let cached = match scope {
+            Scope::Global => &self.constants[handle.index()],
+            Scope::Function => &self.frame().expressions[handle.index()],
+        };
+        if let Some(value) = cached {
+            return Ok(value.clone());
+        }
+        let value = self.expression(scope, handle)?;
+        match scope {
+            Scope::Global => self.constants[handle.index()] = Some(value.clone()),
+            Scope::Function => self.frame_mut().expressions[handle.index()] = Some(value.clone()),
+        }
+        Ok(value)
+    }
+
+    fn expression(&mut self, scope: Scope, handle: Handle<Expression>) -> Result<Value> {
+        let module = self.module;
+        let function = self.frame().function;
+        let expression = match scope {
+            Scope::Global => &module.global_expressions[handle],
+            Scope::Function => &function.expressions[handle],
+        };
+        Ok(match expression {
+            Expression::Literal(literal) => Value::from_literal(*literal),
+            Expression::Constant(constant) => {
+                self.operand(Scope::Global, module.constants[*constant].init)?
+            }
+            Expression::ZeroValue(ty) => Value::zero(module, *ty)?,
+            Expression::Compose { ty, components } => {
+                let components = components
+                    .iter()
+                    .map(|&component| self.operand(scope, component))
+                    .collect::<Result<Vec<_>>>()?;
+                if let TypeInner::Vector { .. } = module.types[*ty].inner {
+                    // vectors can be composed of smaller vectors
+                    let flattened = components
+                        .into_iter()
+                        .flat_map(|component| match component {
+                            Value::Composite(components) => components,
+                            scalar => vec![scalar],
+                        })
+                        .collect();
+                    Value::Composite(flattened)
+                } else {
+                    Value::Composite(components)
+                }
+            }
+            Expression::Access { base, index } => {
+                let index = self.operand(scope, *index)?.as_index()?;
+                self.access(scope, *base, index)?
+            }
+            Expression::AccessIndex { base, index } => {
+                self.access(scope, *base, *index as usize)?
+            }
+            Expression::Splat { size, value } => {
+                Value::Composite(vec![self.operand(scope, *value)?; *size as usize])
+            }
+            Expression::Swizzle {
+                size,
+                vector,
+                pattern,
+            } => {
+                let vector = self.operand(scope, *vector)?;
+                let components = pattern[..*size as usize]
+                    .iter()
+                    .map(|&component| vector.component(component as usize))
+                    .collect::<Result<_>>()?;
+                Value::Composite(components)
+            }
+            Expression::FunctionArgument(index) => self.frame().arguments[*index as usize].clone(),
+            Expression::GlobalVariable(variable) => {
+                if module.global_variables[*variable].space == AddressSpace::Handle {
+                    Value::Resource
+                } else {
+                    Value::Pointer(Pointer {
+                        root: Root::Global(*variable),
+                        path: Vec::new(),
+                    })
+                }
+            }
+            Expression::LocalVariable(variable) => Value::Pointer(Pointer {
+                root: Root::Local {
+                    frame: self.frames.len() - 1,
+                    variable: *variable,
+                },
+                path: Vec::new(),
+            }),
+            Expression::Load { pointer } => {
+                let pointer = self.operand(scope, *pointer)?.into_pointer()?;
+                self.pointee(&pointer)?.clone()
+            }
+            Expression::Unary { op, expr } => self.operand(scope, *expr)?.unary(*op)?,
+            Expression::Binary { op, left, right } => {
+                let left = self.operand(scope, *left)?;
+                let right = self.operand(scope, *right)?;
+                left.binary(*op, &right)?
+            }
+            Expression::Select {
+                condition,
+                accept,
+                reject,
+            } => {
+                let condition = self.operand(scope, *condition)?;
+                let accept = self.operand(scope, *accept)?;
+                let reject = self.operand(scope, *reject)?;
+                if let Value::Bool(condition) = condition {
+                    if condition { accept } else { reject }
+                } else {
+                    condition.zip3(&accept, &reject, &mut |condition, accept, reject| {
+                        Ok(if condition.as_bool()? {
+                            accept.clone()
+                        } else {
+                            reject.clone()
+                        })
+                    })?
+                }
+            }
+            // neighbouring invocations don't exist, every value is treated as uniform
+            Expression::Derivative { expr, .. } => self
+                .operand(scope, *expr)?
+                .map(&mut |_| Ok(Value::F32(0.0)))?,
+            Expression::Relational { fun, argument } => {
+                let argument = self.operand(scope, *argument)?;
+                match fun {
+                    RelationalFunction::All | RelationalFunction::Any => {
+                        let components = match &argument {
+                            Value::Composite(components) => components.clone(),
+                            scalar => vec![scalar.clone()],
+                        };
+                        let mut bools = components.iter().map(Value::as_bool);
+                        Value::Bool(if *fun == RelationalFunction::All {
+                            bools.try_fold(true, |all, value| value.map(|value| all && value))?
+                        } else {
+                            bools.try_fold(false, |any, value| value.map(|value| any || value))?
+                        })
+                    }
+                    RelationalFunction::IsNan => {
+                        argument.map(&mut |value| Ok(Value::Bool(value.as_f32()?.is_nan())))?
+                    }
+                    RelationalFunction::IsInf => {
+                        argument.map(&mut |value| Ok(Value::Bool(value.as_f32()?.is_infinite())))?
+                    }
+                }
+            }
+            Expression::Math {
+                fun,
+                arg,
+                arg1,
+                arg2,
+                arg3,
+            } => {
+                let args = [Some(*arg), *arg1, *arg2, *arg3]
+                    .into_iter()
+                    .flatten()
+                    .map(|arg| self.operand(scope, arg))
+                    .collect::<Result<Vec<_>>>()?;
+                math::evaluate(*fun, &args)?
+            }
+            Expression::As {
+                expr,
+                kind,
+                convert,
+            } => self.operand(scope, *expr)?.cast(*kind, convert.is_some())?,
+            Expression::CallResult(_) => {
+                return Err("The result of a call is used before the call".to_owned());
+            }
+            Expression::ImageSample { .. }
+            | Expression::ImageLoad { .. }
+            | Expression::ImageQuery { .. } => {
+                return Err("Textures are not supported by the CPU renderer".to_owned());
+            }
+            other => {
+                let name = variant_name(other);
+                return Err(format!(
+                    "{name} expressions are not supported by the CPU renderer"
+                ));
+            }
+        })
+    }
+
+    /// Component of a value or a pointer to the component of a pointer
+    fn access(&mut self, scope: Scope, base: Handle<Expression>, index: usize) -> Result<Value> {
+        match self.operand(scope, base)? {
+            Value::Pointer(mut pointer) => {
+                pointer.path.push(index);
+                Ok(Value::Pointer(pointer))
+            }
+            value => value.component(index),
+        }
+    }
+
+    fn pointee(&mut self, pointer: &Pointer) -> Result<&mut Value> {
+        let root = match pointer.root {
+            Root::Local { frame, variable } => &mut self.frames[frame].locals[variable.index()],
+            Root::Global(variable) => self.global(variable)?,
+        };
+        root.at_path_mut(&pointer.path)
+    }
+
+    fn global(&mut self, handle: Handle<GlobalVariable>) -> Result<&mut Value> {
+        if self.variables[handle.index()].is_none() {
+            let variable = &self.module.global_variables[handle];
+            let value = match (variable.space, variable.binding.as_ref()) {
+                (AddressSpace::Private | AddressSpace::WorkGroup, _) => match variable.init {
+                    Some(init) => self.operand(Scope::Global, init)?,
+                    None => Value::zero(self.module, variable.ty)?,
+                },
+                (AddressSpace::Uniform, Some(binding))
+                    if binding.group == 0 && binding.binding == 0 =>
+                {
+                    self.uniforms(variable)?
+                }
+                (AddressSpace::Uniform, Some(binding))
+                    if binding.group == params::GROUP && binding.binding == params::BINDING =>
+                {
+                    Value::from_bytes(self.module, variable.ty, self.params)?
+                }
+                _ => {
+                    let name = variable.name.as_deref().unwrap_or("The global variable");
+                    return Err(format!("{name} is not available in the CPU renderer"));
+                }
+            };
+            self.variables[handle.index()] = Some(value);
+        }
+        Ok(self.variables[handle.index()]
+            .as_mut()
+            .expect("Should be initialized above"))
+    }
+
+    /// Built-in uniforms, read by the offsets of the members,
+    /// the SPIR-V modules may have no member names
+    fn uniforms(&self, variable: &GlobalVariable) -> Result<Value> {
+        Value::from_bytes(self.module, variable.ty, bytemuck::bytes_of(self.uniforms))
+    }
+}
+
+/// Name of the statement or expression variant for the error messages
+fn variant_name(variant: &impl std::fmt::Debug) -> String {
+    let debug = format!("{variant:?}");
+    debug
+        .split([' ', '(', '{'])
+        .next()
+        .unwrap_or_default()
+        .to_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::interpreter::tests::call;
+    use crate::language::Shader;
+
+    fn call_wgsl(source: &str, name: &str, arguments: Vec<Value>) -> Result<Option<Value>> {
+        call(&Shader::Wgsl(source.to_owned()), name, arguments)
+    }
+
+    #[test]
+    fn switch_falls_through() {
+        // the only fall-through of WGSL is a case with several selectors
+        let wgsl = "
+            fn pick(x: i32) -> i32 {
+                var y = 0;
+                switch x {
+                    case 1, 2 { y = 12; }
+                    case 3 { y = 3; }
+                    default { y = -1; }
+                }
+                return y;
+            }
+        ";
+        for (x, y) in [(1, 12), (2, 12), (3, 3), (4, -1)] {
+            assert_eq!(
+                call_wgsl(wgsl, "pick", vec![Value::I32(x)]),
+                Ok(Some(Value::I32(y)))
+            );
+        }
+
+        let glsl = "
+            int pick(int x) {
+                int y = 0;
+                switch (x) {
+                case 1:
+                    y += 1;
+                case 2:
+                    y += 10;
+                    break;
+                default:
+                    y = 100;
+                }
+                return y;
+            }
+            void main() {}
+        ";
+        let shader = Shader::Glsl(naga::ShaderStage::Fragment, glsl.to_owned());
+        for (x, y) in [(1, 11), (2, 10), (3, 100)] {
+            assert_eq!(
+                call(&shader, "pick", vec![Value::I32(x)]),
+                Ok(Some(Value::I32(y)))
+            );
+        }
+    }
+
+    #[test]
+    fn loop_continue_and_break_if() {
+        // `continue` still runs the continuing block
+        let wgsl = "
+            fn odd_sum(last: i32) -> i32 {
+                var i = 0;
+                var sum = 0;
+                loop {
+                    i += 1;
+                    if i % 2 == 0 {
+                        continue;
+                    }
+                    sum += i;
+                    continuing {
+                        break if i >= last;
+                    }
+                }
+                return sum;
+            }
+        ";
+        assert_eq!(
+            call_wgsl(wgsl, "odd_sum", vec![Value::I32(9)]),
+            Ok(Some(Value::I32(1 + 3 + 5 + 7 + 9)))
+        );
+        assert_eq!(
+            call_wgsl(wgsl, "odd_sum", vec![Value::I32(10)]),
+            Ok(Some(Value::I32(1 + 3 + 5 + 7 + 9)))
+        );
+    }
+
+    #[test]
+    fn endless_loop_fails() {
+        let wgsl = "
+            fn endless(step: i32) -> i32 {
+                var i = 0;
+                loop {
+                    i += step;
+                    if i < 0 {
+                        break;
+                    }
+                }
+                return i;
+            }
+        ";
+        let error = call_wgsl(wgsl, "endless", vec![Value::I32(0)])
+            .expect_err("Should stop after MAX_ITERATIONS");
+        assert!(error.contains(&MAX_ITERATIONS.to_string()), "{error}");
+    }
+}
@@ -0,0 +1,313 @@
+use naga::{BinaryOperator, MathFunction};
+
+use super::value::{Value, dot};
+
+type Result<T> = std::result::Result<T, String>;
+
+/// Evaluates a built-in function, `args` holds the present arguments in order
+pub fn evaluate(fun: MathFunction, args: &[Value]) -> Result<Value> {
+    use MathFunction as Mf;
+    let arg = |index: usize| {
+        args.get(index)
+            .ok_or_else(|| format!("{fun:?} is missing an argument"))
+    };
+    let e = arg(0)?;
+    Ok(match fun {
+        Mf::Abs => e.map(&mut |value| match value {
+            Value::F32(value) => Ok(Value::F32(value.abs())),
+            Value::I32(value) => Ok(Value::I32(value.wrapping_abs())),
+            Value::U32(value) => Ok(Value::U32(*value)),
+            other => Err(format!("Can't apply abs to {other:?}")),
+        })?,
+        Mf::Min => e.zip(arg(1)?, &mut |a, b| pick(a, b, |a, b| a < b))?,
+        Mf::Max => e.zip(arg(1)?, &mut |a, b| pick(a, b, |a, b| a > b))?,
+        Mf::Clamp => e.zip3(arg(1)?, arg(2)?, &mut |value, low, high| {
+            let value = pick(value, low, |a, b| a > b)?;
+            pick(&value, high, |a, b| a < b)
+        })?,
+        Mf::Saturate => floats(e, |x| x.clamp(0.0, 1.0))?,
+        Mf::Cos => floats(e, f32::cos)?,
+        Mf::Cosh => floats(e, f32::cosh)?,
+        Mf::Sin => floats(e, f32::sin)?,
+        Mf::Sinh => floats(e, f32::sinh)?,
+        Mf::Tan => floats(e, f32::tan)?,
+        Mf::Tanh => floats(e, f32::tanh)?,
+        Mf::Acos => floats(e, f32::acos)?,
+        Mf::Asin => floats(e, f32::asin)?,
+        Mf::Atan => floats(e, f32::atan)?,
+        Mf::Atan2 => floats2(e, arg(1)?, f32::atan2)?,
+        Mf::Asinh => floats(e, f32::asinh)?,
+        Mf::Acosh => floats(e, f32::acosh)?,
+        Mf::Atanh => floats(e, f32::atanh)?,
+        Mf::Radians => floats(e, f32::to_radians)?,
+        Mf::Degrees => floats(e, f32::to_degrees)?,
+        Mf::Ceil => floats(e, f32::ceil)?,
+        Mf::Floor => floats(e, f32::floor)?,
+        Mf::Round => floats(e, f32::round_ties_even)?,
+        Mf::Fract => floats(e, |x| x - x.floor())?,
+        Mf::Trunc => floats(e, f32::trunc)?,
+        // the result struct has the fractional part first
+        Mf::Modf => Value::Composite(vec![floats(e, f32::fract)?, floats(e, f32::trunc)?]),
+        Mf::Ldexp => e.zip(arg(1)?, &mut |x, exponent| {
+            Ok(Value::F32(x.as_f32()? * 2f32.powf(exponent.as_f32()?)))
+        })?,
+        Mf::Exp => floats(e, f32::exp)?,
+        Mf::Exp2 => floats(e, f32::exp2)?,
+        Mf::Log => floats(e, f32::ln)?,
+        Mf::Log2 => floats(e, f32::log2)?,
+        Mf::Pow => floats2(e, arg(1)?, f32::powf)?,
+        Mf::Sqrt => floats(e, f32::sqrt)?,
+        Mf::InverseSqrt => floats(e, |x| 1.0 / x.sqrt())?,
+        Mf::Dot => dot(e, arg(1)?)?,
+        Mf::Outer => {
+            let columns = arg(1)?
+                .components()?
+                .iter()
+                .map(|component| e.binary(BinaryOperator::Multiply, component))
+                .collect::<Result<_>>()?;
+            Value::Composite(columns)
+        }
+        Mf::Cross => {
+            let [ax, ay, az] = vec3(e)?;
+            let [bx, by, bz] = vec3(arg(1)?)?;
+            Value::from_floats(&[ay * bz - az * by, az * bx - ax * bz, ax * by - ay * bx])
+        }
+        Mf::Distance => Value::F32(length(&e.binary(BinaryOperator::Subtract, arg(1)?)?)?),
+        Mf::Length => Value::F32(length(e)?),
+        Mf::Normalize => e.binary(BinaryOperator::Divide, &Value::F32(length(e)?))?,
+        Mf::FaceForward => {
+            if dot(arg(1)?, arg(2)?)?.as_f32()? < 0.0 {
+                e.clone()
+            } else {
+                e.unary(naga::UnaryOperator::Negate)?
+            }
+        }
+        Mf::Reflect => {
+            let normal = arg(1)?;
+            let scale = Value::F32(2.0 * dot(normal, e)?.as_f32()?);
+            e.binary(
+                BinaryOperator::Subtract,
+                &normal.binary(BinaryOperator::Multiply, &scale)?,
+            )?
+        }
+        Mf::Refract => {
+            let normal = arg(1)?;
+            let eta = arg(2)?.as_f32()?;
+            let cos = dot(normal, e)?.as_f32()?;
+            let k = 1.0 - eta * eta * (1.0 - cos * cos);
+            if k < 0.0 {
+                e.map(&mut |_| Ok(Value::F32(0.0)))?
+            } else {
+                let incident = e.binary(BinaryOperator::Multiply, &Value::F32(eta))?;
+                let scale = Value::F32(eta * cos + k.sqrt());
+                incident.binary(
+                    BinaryOperator::Subtract,
+                    &normal.binary(BinaryOperator::Multiply, &scale)?,
+                )?
+            }
+        }
+        Mf::Sign => e.map(&mut |value| match value {
+            Value::F32(value) if *value == 0.0 => Ok(Value::F32(0.0)),
+            Value::F32(value) => Ok(Value::F32(value.signum())),
+            Value::I32(value) => Ok(Value::I32(value.signum())),
+            other => Err(format!("Can't apply sign to {other:?}")),
+        })?,
+        Mf::Fma => e.zip3(arg(1)?, arg(2)?, &mut |a, b, c| {
+            Ok(Value::F32(a.as_f32()?.mul_add(b.as_f32()?, c.as_f32()?)))
+        })?,
+        Mf::Mix => e.zip3(arg(1)?, arg(2)?, &mut |a, b, t| {
+            let t = t.as_f32()?;
+            Ok(Value::F32(a.as_f32()? * (1.0 - t) + b.as_f32()? * t))
+        })?,
+        Mf::Step => floats2(e, arg(1)?, |edge, x| if edge <= x { 1.0 } else { 0.0 })?,
+        Mf::SmoothStep => e.zip3(arg(1)?, arg(2)?, &mut |low, high, x| {
+            let (low, high) = (low.as_f32()?, high.as_f32()?);
+            let t = ((x.as_f32()? - low) / (high - low)).clamp(0.0, 1.0);
+            Ok(Value::F32(t * t * (3.0 - 2.0 * t)))
+        })?,
+        Mf::Transpose => {
+            let columns = matrix(e)?;
+            let rows = columns.first().map_or(0, Vec::len);
+            let transposed: Vec<_> = (0..rows)
+                .map(|row| Value::from_floats(&columns.iter().map(|c| c[row]).collect::<Vec<_>>()))
+                .collect();
+            Value::Composite(transposed)
+        }
+        Mf::Determinant => Value::F32(determinant(&matrix(e)?)),
+        Mf::QuantizeToF16 => floats(e, |x| half::f16::from_f32(x).to_f32())?,
+        Mf::CountTrailingZeros => bits(e, u32::trailing_zeros)?,
+        Mf::CountLeadingZeros => bits(e, u32::leading_zeros)?,
+        Mf::CountOneBits => bits(e, u32::count_ones)?,
+        Mf::ReverseBits => bits(e, u32::reverse_bits)?,
+        Mf::FirstTrailingBit => bits(e, |x| if x == 0 { u32::MAX } else { x.trailing_zeros() })?,
+        Mf::FirstLeadingBit => e.map(&mut |value| match value {
+            Value::U32(0) => Ok(Value::U32(u32::MAX)),
+            Value::U32(x) => Ok(Value::U32(31 - x.leading_zeros())),
+            Value::I32(0 | -1) => Ok(Value::I32(-1)),
+            // the first bit different from the sign bit
+            Value::I32(x) if *x < 0 => Ok(Value::I32(31 - (!x).leading_zeros() as i32)),
+            Value::I32(x) => Ok(Value::I32(31 - x.leading_zeros() as i32)),
+            other => Err(format!("Can't apply firstLeadingBit to {other:?}")),
+        })?,
+        Mf::ExtractBits => {
+            let offset = arg(1)?.as_index()?.min(32) as u32;
+            let count = (arg(2)?.as_index()?.min(32) as u32).min(32 - offset);
+            e.map(&mut |value| {
+                if count == 0 {
+                    return value.binary(BinaryOperator::Subtract, value);
+                }
+                match value {
+                    Value::U32(x) => Ok(Value::U32(
+                        x.wrapping_shr(offset) & (u32::MAX >> (32 - count)),
+                    )),
+                    // sign extended from the last extracted bit
+                    Value::I32(x) => Ok(Value::I32(
+                        (x << (32 - offset - count)).wrapping_shr(32 - count),
+                    )),
+                    other => Err(format!("Can't apply extractBits to {other:?}")),
+                }
+            })?
+        }
+        Mf::InsertBits => {
+            let offset = arg(2)?.as_index()?.min(32) as u32;
+            let count = (arg(3)?.as_index()?.min(32) as u32).min(32 - offset);
+            let mask = if count == 0 {
+                0
+            } else {
+                (u32::MAX >> (32 - count)) << offset
+            };
+            e.zip(arg(1)?, &mut |value, new| match (value, new) {
+                (Value::U32(x), Value::U32(y)) => {
+                    Ok(Value::U32((x & !mask) | (y.wrapping_shl(offset) & mask)))
+                }
+                (Value::I32(x), Value::I32(y)) => Ok(Value::I32(
+                    ((*x as u32 & !mask) | ((*y as u32).wrapping_shl(offset) & mask)) as i32,
+                )),
+                (value, _) => Err(format!("Can't apply insertBits to {value:?}")),
+            })?
+        }
+        Mf::Pack4x8unorm => pack(e, 8, |x| (x.clamp(0.0, 1.0) * 255.0).round() as u32)?,
+        Mf::Pack4x8snorm => pack(e, 8, |x| {
+            ((x.clamp(-1.0, 1.0) * 127.0).round() as i32 as u32) & 0xff
+        })?,
+        Mf::Pack2x16unorm => pack(e, 16, |x| (x.clamp(0.0, 1.0) * 65535.0).round() as u32)?,
+        Mf::Pack2x16snorm => pack(e, 16, |x| {
+            ((x.clamp(-1.0, 1.0) * 32767.0).round() as i32 as u32) & 0xffff
+        })?,
+        Mf::Pack2x16float => pack(e, 16, |x| half::f16::from_f32(x).to_bits().into())?,
+        Mf::Unpack4x8unorm => unpack(e, 8, |x| x as f32 / 255.0)?,
+        Mf::Unpack4x8snorm => unpack(e, 8, |x| (f32::from(x as u8 as i8) / 127.0).max(-1.0))?,
+        Mf::Unpack2x16unorm => unpack(e, 16, |x| x as f32 / 65535.0)?,
+        Mf::Unpack2x16snorm => unpack(e, 16, |x| (f32::from(x as u16 as i16) / 32767.0).max(-1.0))?,
+        Mf::Unpack2x16float => unpack(e, 16, |x| half::f16::from_bits(x as u16).to_f32())?,
+        fun => return Err(format!("{fun:?} is not supported by the CPU renderer")),
+    })
+}
+
+/// Returns `a` if `choose_a(a, b)`, otherwise `b`
+fn pick(a: &Value, b: &Value, choose_a: fn(f64, f64) -> bool) -> Result<Value> {
+    let number = |value: &Value| match value {
+        Value::F32(value) => Ok(f64::from(*value)),
+        Value::I32(value) => Ok(f64::from(*value)),
+        Value::U32(value) => Ok(f64::from(*value)),
+        other => Err(format!("Expected a number, found {other:?}")),
+    };
+    Ok(if choose_a(number(a)?, number(b)?) {
+        a.clone()
+    } else {
+        b.clone()
+    })
+}
+
+fn floats(value: &Value, f: impl Fn(f32) -> f32) -> Result<Value> {
+    value.map(&mut |value| Ok(Value::F32(f(value.as_f32()?))))
+}
+
+fn floats2(a: &Value, b: &Value, f: impl Fn(f32, f32) -> f32) -> Result<Value> {
+    a.zip(b, &mut |a, b| Ok(Value::F32(f(a.as_f32()?, b.as_f32()?))))
+}
+
+/// Applies `f` to the bits of an integer, keeping the signedness
+fn bits(value: &Value, f: impl Fn(u32) -> u32) -> Result<Value> {
+    value.map(&mut |value| match value {
+        Value::U32(x) => Ok(Value::U32(f(*x))),
+        Value::I32(x) => Ok(Value::I32(f(*x as u32) as i32)),
+        other => Err(format!("Expected an integer, found {other:?}")),
+    })
+}
+
+fn length(value: &Value) -> Result<f32> {
+    Ok(value.floats()?.iter().map(|x| x * x).sum::<f32>().sqrt())
+}
+
+fn vec3(value: &Value) -> Result<[f32; 3]> {
+    value
+        .floats()?
+        .try_into()
+        .map_err(|_| "Expected a vec3".to_owned())
+}
+
+fn matrix(value: &Value) -> Result<Vec<Vec<f32>>> {
+    value.components()?.iter().map(Value::floats).collect()
+}
+
+/// Laplace expansion along the first column
+fn determinant(columns: &[Vec<f32>]) -> f32 {
+    if columns.len() == 1 {
+        return columns[0][0];
+    }
+    (0..columns.len())
+        .map(|row| {
+            let minor: Vec<Vec<f32>> = columns[1..]
+                .iter()
+                .map(|column| {
+                    column
+                        .iter()
+                        .enumerate()
+                        .filter(|&(index, _)| index != row)
+                        .map(|(_, &x)| x)
+                        .collect()
+                })
+                .collect();
+            let sign = if row % 2 == 0 { 1.0 } else { -1.0 };
+            sign * columns[0][row] * determinant(&minor)
+        })
+        .sum()
+}
+
+/// Packs the components into `width`-bit fields, the first one in the lowest bits
+fn pack(value: &Value, width: u32, f: impl Fn(f32) -> u32) -> Result<Value> {
+    let packed = value
+        .floats()?
+        .iter()
+        .enumerate()
+        .fold(0, |packed, (index, &x)| {
+            packed | (f(x) << (index as u32 * width))
+        });
+    Ok(Value::U32(packed))
+}
+
+fn unpack(value: &Value, width: u32, f: impl Fn(u32) -> f32) -> Result<Value> {
+    let Value::U32(packed) = value else {
+        return Err(format!("Expected a u32, found {value:?}"));
+    };
+    let mask = u32::MAX >> (32 - width);
+    let floats: Vec<_> = (0..32 / width)
+        .map(|index| f((packed >> (index * width)) & mask))
+        .collect();
+    Ok(Value::from_floats(&floats))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_ties_to_even() {
+        let rounded = evaluate(
+            MathFunction::Round,
+            &[Value::from_floats(&[0.5, 1.5, 2.5, -2.5])],
+        );
+        assert_eq!(rounded, Ok(Value::from_floats(&[0.0, 2.0, 2.0, -2.0])));
+    }
+}
@@ -0,0 +1,461 @@
+//! Reference renderer running the image pass on the CPU, one invocation at a time.
+//!
+//! It's slow and supports only a subset of the shaders: textures, storage and the compute pass
+//! are not available, derivatives are zero. It helps to tell driver bugs from shader bugs.
+
+mod eval;
+mod math;
+mod value;
+
+use image::RgbaImage;
+use naga::{Binding, BuiltIn, EntryPoint, Interpolation, Module, ShaderStage, TypeInner};
+
+use crate::entry_points::{EntryPoints, Selection};
+use crate::language::Shader;
+use crate::scene::globals::Globals;
+use crate::scene::rectangle;
+use crate::validator;
+use eval::Interpreter;
+use value::Value;
+
+type Result<T> = std::result::Result<T, String>;
+
+/// Output of the vertex stage
+struct Vertex {
+    /// Clip space position
+    position: [f32; 4],
+    varyings: Vec<Varying>,
+}
+
+struct Varying {
+    location: u32,
+    interpolation: Interpolation,
+    value: Value,
+}
+
+/// Renders the image pass of the shader at the size of `globals.resolution`,
+/// `params` is the content of the buffer of the parameters
+pub fn render(
+    shader: &Shader,
+    selection: &Selection,
+    globals: &Globals,
+    params: &[u8],
+) -> Result<RgbaImage> {
+    let (module, _, composed) =
+        validator::parse(shader).map_err(|diagnostic| diagnostic.message)?;
+    // fragment shaders in the other languages get the full-screen triangle of WGSL
+    let fullscreen = match shader {
+        Shader::Glsl(..) | Shader::SpirV(_) if composed.fragment_only => Some(
+            validator::parse(&Shader::Wgsl(String::new()))
+                .map_err(|diagnostic| diagnostic.message)?
+                .0,
+        ),
+        _ => None,
+    };
+    let vertex_module = fullscreen.as_ref().unwrap_or(&module);
+    let selection = EntryPoints::from_module(&module).resolve(selection);
+    let vertex_entry = entry_point(
+        vertex_module,
+        ShaderStage::Vertex,
+        selection.vertex.as_deref(),
+    )?;
+    let fragment_entry = entry_point(
+        &module,
+        ShaderStage::Fragment,
+        selection.fragment.as_deref(),
+    )?;
+
+    // the same geometry as the image pass draws
+    let (positions, triangles): (Vec<[f32; 3]>, Vec<[usize; 3]>) = if composed.fragment_only {
+        (vec![[0.0; 3]; 3], vec![[0, 1, 2]])
+    } else {
+        let positions = rectangle::POSITIONS.iter().map(|&[x, y]| [x, y, 0.0]);
+        let triangles = rectangle::INDICES
+            .chunks(3)
+            .map(|indices| [0, 1, 2].map(|corner| usize::from(indices[corner])));
+        (positions.collect(), triangles.collect())
+    };
+
+    let mut interpreter = Interpreter::new(vertex_module, globals, params);
+    let vertices = positions
+        .iter()
+        .enumerate()
+        .map(|(index, &position)| {
+            run_vertex(
+                &mut interpreter,
+                vertex_module,
+                vertex_entry,
+                index,
+                position,
+            )
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    let [width, height] = globals.resolution.map(|size| size as u32);
+    // black and opaque, the same as the cleared image pass
+    let mut image = RgbaImage::from_pixel(width, height, image::Rgba([0, 0, 0, 255]));
+    if width == 0 || height == 0 {
+        return Ok(image);
+    }
+
+    // every thread draws a band of rows
+    let threads = std::thread::available_parallelism().map_or(1, |threads| threads.get());
+    let rows_per_thread = (height as usize).div_ceil(threads);
+    let row_bytes = width as usize * 4;
+    std::thread::scope(|scope| {
+        let handles: Vec<_> = image
+            .chunks_mut(rows_per_thread * row_bytes)
+            .enumerate()
+            .map(|(band, pixels)| {
+                let rasterizer = Rasterizer {
+                    module: &module,
+                    entry: fragment_entry,
+                    width,
+                    height,
+                    first_row: (band * rows_per_thread) as u32,
+                };
+                let (vertices, triangles) = (&vertices, &triangles);
+                scope.spawn(move || {
+                    let mut interpreter = Interpreter::new(rasterizer.module, globals, params);
+                    rasterizer.draw(&mut interpreter, vertices, triangles, pixels)
+                })
+            })
+            .collect();
+        handles
+            .into_iter()
+            .try_for_each(|handle| handle.join().expect("Should not panic while rendering"))
+    })?;
+    Ok(image)
+}
+
+fn entry_point<'a>(
+    module: &'a Module,
+    stage: ShaderStage,
+    name: Option<&str>,
+) -> Result<&'a EntryPoint> {
+    module
+        .entry_points
+        .iter()
+        .find(|entry| entry.stage == stage && name.is_none_or(|name| entry.name == name))
+        .ok_or_else(|| format!("The shader has no {stage:?} entry point"))
+}
+
+fn run_vertex<'a>(
+    interpreter: &mut Interpreter<'a>,
+    module: &Module,
+    entry: &'a EntryPoint,
+    index: usize,
+    position: [f32; 3],
+) -> Result<Vertex> {
+    let arguments = inputs(module, entry, |binding| match binding {
+        Binding::Location { location: 0, .. } => Ok(Value::from_floats(&position)),
+        Binding::BuiltIn(BuiltIn::VertexIndex) => Ok(Value::U32(index as u32)),
+        Binding::BuiltIn(BuiltIn::InstanceIndex) => Ok(Value::U32(0)),
+        binding => Err(format!("The vertex input {binding:?} is not provided")),
+    })?;
+    let result = interpreter
+        .run(&entry.function, arguments)?
+        .ok_or_else(|| format!("{} returned no value", entry.name))?;
+
+    let mut vertex = Vertex {
+        position: [0.0; 4],
+        varyings: Vec::new(),
+    };
+    for (binding, value) in outputs(module, entry, result)? {
+        match binding {
+            Binding::BuiltIn(BuiltIn::Position { .. }) => {
+                vertex.position = value
+                    .floats()?
+                    .try_into()
+                    .map_err(|_| "The position is not a vec4".to_owned())?;
+            }
+            Binding::Location {
+                location,
+                interpolation,
+                ..
+            } => vertex.varyings.push(Varying {
+                location: *location,
+                interpolation: interpolation.unwrap_or(Interpolation::Perspective),
+                value,
+            }),
+            _ => (),
+        }
+    }
+    Ok(vertex)
+}
+
+/// Arguments of the entry point, the values of the bindings come from `lookup`
+fn inputs(
+    module: &Module,
+    entry: &EntryPoint,
+    lookup: impl Fn(&Binding) -> Result<Value>,
+) -> Result<Vec<Value>> {
+    entry
+        .function
+        .arguments
+        .iter()
+        .map(|argument| match &argument.binding {
+            Some(binding) => lookup(binding),
+            None => {
+                let TypeInner::Struct { members, .. } = &module.types[argument.ty].inner else {
+                    return Err(format!("An argument of {} has no binding", entry.name));
+                };
+                members
+                    .iter()
+                    .map(|member| match &member.binding {
+                        Some(binding) => lookup(binding),
+                        None => Err(format!("A member of {} input has no binding", entry.name)),
+                    })
+                    .collect::<Result<_>>()
+                    .map(Value::Composite)
+            }
+        })
+        .collect()
+}
+
+/// Bound values of the entry point result
+fn outputs<'a>(
+    module: &'a Module,
+    entry: &'a EntryPoint,
+    value: Value,
+) -> Result<Vec<(&'a Binding, Value)>> {
+    let Some(result) = &entry.function.result else {
+        return Ok(Vec::new());
+    };
+    if let Some(binding) = &result.binding {
+        return Ok(vec![(binding, value)]);
+    }
+    let TypeInner::Struct { members, .. } = &module.types[result.ty].inner else {
+        return Ok(Vec::new());
+    };
+    Ok(members
+        .iter()
+        .zip(value.components()?.iter().cloned())
+        .filter_map(|(member, value)| Some((member.binding.as_ref()?, value)))
+        .collect())
+}
+
+/// Draws the triangles into a band of rows with the fragment entry point
+struct Rasterizer<'a> {
+    module: &'a Module,
+    entry: &'a EntryPoint,
+    width: u32,
+    height: u32,
+    first_row: u32,
+}
+
+impl<'a> Rasterizer<'a> {
+    fn draw(
+        &self,
+        interpreter: &mut Interpreter<'a>,
+        vertices: &[Vertex],
+        triangles: &[[usize; 3]],
+        pixels: &mut [u8],
+    ) -> Result<()> {
+        let rows = (pixels.len() / (self.width as usize * 4)) as u32;
+        for (primitive, triangle) in triangles.iter().enumerate() {
+            let corners = triangle.map(|index| &vertices[index]);
+            // clipping isn't implemented, the triangles behind the camera are skipped
+            if corners.iter().any(|corner| corner.position[3] <= 0.0) {
+                continue;
+            }
+            let ndc = corners.map(|corner| {
+                let [x, y, z, w] = corner.position;
+                [x / w, y / w, z / w]
+            });
+            // counter-clockwise triangles are the front faces, the back ones are culled
+            if edge(ndc[0], ndc[1], ndc[2]) <= 0.0 {
+                continue;
+            }
+            let screen = ndc.map(|[x, y, z]| {
+                [
+                    (x * 0.5 + 0.5) * self.width as f32,
+                    (0.5 - y * 0.5) * self.height as f32,
+                    z,
+                ]
+            });
+            let area = edge(screen[0], screen[1], screen[2]);
+
+            let min_x = screen
+                .iter()
+                .map(|p| p[0])
+                .fold(f32::MAX, f32::min)
+                .max(0.0) as u32;
+            let max_x = screen.iter().map(|p| p[0]).fold(f32::MIN, f32::max).ceil();
+            let min_y = screen
+                .iter()
+                .map(|p| p[1])
+                .fold(f32::MAX, f32::min)
+                .max(0.0) as u32;
+            let max_y = screen.iter().map(|p| p[1]).fold(f32::MIN, f32::max).ceil();
+            let max_x = (max_x as u32).min(self.width);
+            let rows_end = (max_y as u32).min(self.first_row + rows);
+
+            for y in min_y.max(self.first_row)..rows_end {
+                for x in min_x..max_x {
+                    let center = [x as f32 + 0.5, y as f32 + 0.5, 0.0];
+                    let barycentric = [
+                        edge(screen[1], screen[2], center) / area,
+                        edge(screen[2], screen[0], center) / area,
+                        edge(screen[0], screen[1], center) / area,
+                    ];
+                    if barycentric.iter().any(|&weight| weight < 0.0) {
+                        continue;
+                    }
+                    let color = self
+                        .shade(interpreter, corners, screen, barycentric, center, primitive)
+                        .map_err(|e| format!("{e} at the pixel ({x}, {y})"))?;
+                    if let Some(color) = color {
+                        let offset = ((y - self.first_row) * self.width + x) as usize * 4;
+                        pixels[offset..offset + 4].copy_from_slice(&color);
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Color of the pixel, `None` if the fragment was discarded
+    fn shade(
+        &self,
+        interpreter: &mut Interpreter<'a>,
+        corners: [&Vertex; 3],
+        screen: [[f32; 3]; 3],
+        barycentric: [f32; 3],
+        center: [f32; 3],
+        primitive: usize,
+    ) -> Result<Option<[u8; 4]>> {
+        // perspective correct weights, 1/w is interpolated linearly in the screen space
+        let inverse_w: [f32; 3] = [0, 1, 2].map(|i| barycentric[i] / corners[i].position[3]);
+        let sum: f32 = inverse_w.iter().sum();
+        let perspective = inverse_w.map(|weight| weight / sum);
+        let depth: f32 = (0..3).map(|i| barycentric[i] * screen[i][2]).sum();
+
+        let arguments = inputs(self.module, self.entry, |binding| match binding {
+            Binding::BuiltIn(BuiltIn::Position { .. }) => {
+                Ok(Value::from_floats(&[center[0], center[1], depth, sum]))
+            }
+            Binding::BuiltIn(BuiltIn::FrontFacing) => Ok(Value::Bool(true)),
+            Binding::BuiltIn(BuiltIn::PrimitiveIndex) => Ok(Value::U32(primitive as u32)),
+            Binding::BuiltIn(BuiltIn::SampleIndex) => Ok(Value::U32(0)),
+            Binding::BuiltIn(BuiltIn::SampleMask) => Ok(Value::U32(1)),
+            Binding::Location { location, .. } => {
+                let varyings = corners.map(|corner| {
+                    corner
+                        .varyings
+                        .iter()
+                        .find(|varying| varying.location == *location)
+                });
+                let [Some(first), Some(second), Some(third)] = varyings else {
+                    return Err(format!(
+                        "@location({location}) is not written by the vertex stage"
+                    ));
+                };
+                let weights = match first.interpolation {
+                    // the first vertex provokes the flat values
+                    Interpolation::Flat => return Ok(first.value.clone()),
+                    Interpolation::Linear => barycentric,
+                    Interpolation::Perspective => perspective,
+                };
+                first
+                    .value
+                    .zip3(&second.value, &third.value, &mut |a, b, c| {
+                        Ok(Value::F32(
+                            a.as_f32()? * weights[0]
+                                + b.as_f32()? * weights[1]
+                                + c.as_f32()? * weights[2],
+                        ))
+                    })
+            }
+            binding => Err(format!("The fragment input {binding:?} is not provided")),
+        })?;
+
+        let Some(result) = interpreter.run(&self.entry.function, arguments)? else {
+            return Ok(None);
+        };
+        let color = outputs(self.module, self.entry, result)?
+            .into_iter()
+            .find(|(binding, _)| matches!(binding, Binding::Location { location: 0, .. }))
+            .ok_or_else(|| format!("{} has no @location(0) output", self.entry.name))?
+            .1
+            .floats()?;
+        let [r, g, b] = [0, 1, 2].map(|i| encode_srgb(color.get(i).copied().unwrap_or(0.0)));
+        Ok(Some([r, g, b, 255]))
+    }
+}
+
+/// Twice the signed area of the triangle, positive for the counter-clockwise ones
+/// in a space with the `y` axis going up
+fn edge(a: [f32; 3], b: [f32; 3], c: [f32; 3]) -> f32 {
+    (b[0] - a[0]) * (c[1] - a[1]) - (b[1] - a[1]) * (c[0] - a[0])
+}
+
+/// The image pass renders into an sRGB texture, the stored bytes are encoded
+fn encode_srgb(linear: f32) -> u8 {
+    let linear = linear.clamp(0.0, 1.0);
+    let encoded = if linear <= 0.003_130_8 {
+        linear * 12.92
+    } else {
+        1.055 * linear.powf(1.0 / 2.4) - 0.055
+    };
+    (encoded * 255.0).round() as u8
+}
+
+#[cfg(test)]
+pub(super) mod tests {
+    use super::*;
+    use crate::params::BUFFER_SIZE;
+
+    /// Calls the function `name` of the shader, `None` if it was discarded
+    pub fn call(shader: &Shader, name: &str, arguments: Vec<Value>) -> Result<Option<Value>> {
+        let (module, _, _) = validator::parse(shader).map_err(|diagnostic| diagnostic.message)?;
+        let function = module
+            .functions
+            .iter()
+            .map(|(_, function)| function)
+            .find(|function| function.name.as_deref() == Some(name))
+            .ok_or_else(|| format!("No function {name}"))?;
+        let globals = Globals::default();
+        let params = [0; BUFFER_SIZE as usize];
+        Interpreter::new(&module, &globals, &params).run(function, arguments)
+    }
+
+    #[test]
+    fn pattern_1() {
+        // rendered by llvmpipe, green is either 0 or 255
+        let expected = [
+            "................",
+            "................",
+            "###.............",
+            "######..........",
+            "########........",
+            "....#####.......",
+            "......####......",
+            ".......####.....",
+            "........####....",
+            ".........###....",
+            "..........###...",
+            "..........###...",
+            "...........##...",
+            "...........###..",
+            "...........###..",
+            "...........###..",
+        ];
+        let shader = Shader::Wgsl(include_str!("../../shaders/pattern_1.wgsl").to_owned());
+        let mut globals = Globals::default();
+        globals.resolution = [16.0, 16.0];
+        let image = render(&shader, &Selection::default(), &globals, &[0; 16])
+            .expect("Should render pattern_1.wgsl");
+
+        for (y, row) in expected.iter().enumerate() {
+            for (x, pixel) in row.chars().enumerate() {
+                let green = if pixel == '#' { 255 } else { 0 };
+                assert_eq!(
+                    image.get_pixel(x as u32, y as u32).0,
+                    [0, green, 0, 255],
+                    "pixel ({x}, {y})"
+                );
+            }
+        }
+    }
+}
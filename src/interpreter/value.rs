@@ -0,0 +1,515 @@
+use naga::{
+    ArraySize, BinaryOperator, GlobalVariable, Handle, Literal, LocalVariable, Module, ScalarKind,
+    Type, TypeInner, UnaryOperator,
+};
+
+/// Value of an expression or a variable
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Bool(bool),
+    I32(i32),
+    U32(u32),
+    F32(f32),
+    /// Components of a vector, columns of a matrix, elements of an array or members of a struct
+    Composite(Vec<Value>),
+    Pointer(Pointer),
+    /// Texture or sampler, can't be read by the interpreter
+    Resource,
+}
+
+/// Variable and the component indices inside of it
+#[derive(Debug, Clone, PartialEq)]
+pub struct Pointer {
+    pub root: Root,
+    pub path: Vec<usize>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Root {
+    /// Local variable of the function at `frame` in the call stack
+    Local {
+        frame: usize,
+        variable: Handle<LocalVariable>,
+    },
+    Global(Handle<GlobalVariable>),
+}
+
+type Result<T> = std::result::Result<T, String>;
+
+impl Value {
+    /// Value of a variable without an initializer
+    pub fn zero(module: &Module, ty: Handle<Type>) -> Result<Value> {
+        Ok(match &module.types[ty].inner {
+            TypeInner::Scalar(scalar) | TypeInner::Atomic(scalar) => {
+                Value::zero_scalar(scalar.kind)
+            }
+            TypeInner::Vector { size, scalar } => {
+                Value::Composite(vec![Value::zero_scalar(scalar.kind); *size as usize])
+            }
+            TypeInner::Matrix {
+                columns,
+                rows,
+                scalar,
+            } => {
+                let column =
+                    Value::Composite(vec![Value::zero_scalar(scalar.kind); *rows as usize]);
+                Value::Composite(vec![column; *columns as usize])
+            }
+            TypeInner::Array {
+                base,
+                size: ArraySize::Constant(size),
+                ..
+            } => Value::Composite(vec![Value::zero(module, *base)?; size.get() as usize]),
+            TypeInner::Struct { members, .. } => Value::Composite(
+                members
+                    .iter()
+                    .map(|member| Value::zero(module, member.ty))
+                    .collect::<Result<_>>()?,
+            ),
+            TypeInner::Image { .. } | TypeInner::Sampler { .. } => Value::Resource,
+            _ => {
+                let name = module.types[ty].name.as_deref().unwrap_or("runtime-sized");
+                return Err(format!("Values of the {name} type are not supported"));
+            }
+        })
+    }
+
+    /// Value of a host-shareable type read from a buffer,
+    /// members are found by their offsets the same way the GPU does
+    pub fn from_bytes(module: &Module, ty: Handle<Type>, bytes: &[u8]) -> Result<Value> {
+        let scalar = |kind, offset: usize| -> Result<Value> {
+            let word = bytes
+                .get(offset..offset + 4)
+                .ok_or_else(|| "The buffer is smaller than its type".to_owned())?;
+            let word = [word[0], word[1], word[2], word[3]];
+            Ok(match kind {
+                ScalarKind::Sint => Value::I32(i32::from_ne_bytes(word)),
+                ScalarKind::Uint => Value::U32(u32::from_ne_bytes(word)),
+                ScalarKind::Bool => Value::Bool(u32::from_ne_bytes(word) != 0),
+                _ => Value::F32(f32::from_ne_bytes(word)),
+            })
+        };
+        Ok(match &module.types[ty].inner {
+            TypeInner::Scalar(scalar_type) | TypeInner::Atomic(scalar_type) => {
+                scalar(scalar_type.kind, 0)?
+            }
+            TypeInner::Vector {
+                size,
+                scalar: scalar_type,
+            } => Value::Composite(
+                (0..*size as usize)
+                    .map(|index| scalar(scalar_type.kind, index * 4))
+                    .collect::<Result<_>>()?,
+            ),
+            TypeInner::Matrix {
+                columns,
+                rows,
+                scalar: scalar_type,
+            } => {
+                // columns of vec3 are aligned to 16 bytes
+                let stride = if *rows as usize == 2 { 8 } else { 16 };
+                Value::Composite(
+                    (0..*columns as usize)
+                        .map(|column| {
+                            (0..*rows as usize)
+                                .map(|row| scalar(scalar_type.kind, column * stride + row * 4))
+                                .collect::<Result<_>>()
+                                .map(Value::Composite)
+                        })
+                        .collect::<Result<_>>()?,
+                )
+            }
+            TypeInner::Array {
+                base,
+                size: ArraySize::Constant(size),
+                stride,
+            } => Value::Composite(
+                (0..size.get() as usize)
+                    .map(|index| {
+                        let offset = (index * *stride as usize).min(bytes.len());
+                        Value::from_bytes(module, *base, &bytes[offset..])
+                    })
+                    .collect::<Result<_>>()?,
+            ),
+            TypeInner::Struct { members, .. } => Value::Composite(
+                members
+                    .iter()
+                    .map(|member| {
+                        let offset = (member.offset as usize).min(bytes.len());
+                        Value::from_bytes(module, member.ty, &bytes[offset..])
+                    })
+                    .collect::<Result<_>>()?,
+            ),
+            _ => Value::zero(module, ty)?,
+        })
+    }
+
+    fn zero_scalar(kind: ScalarKind) -> Value {
+        match kind {
+            ScalarKind::Sint | ScalarKind::AbstractInt => Value::I32(0),
+            ScalarKind::Uint => Value::U32(0),
+            ScalarKind::Float | ScalarKind::AbstractFloat => Value::F32(0.0),
+            ScalarKind::Bool => Value::Bool(false),
+        }
+    }
+
+    /// 64-bit and 16-bit literals are narrowed to 32 bits
+    pub fn from_literal(literal: Literal) -> Value {
+        match literal {
+            Literal::F64(value) | Literal::AbstractFloat(value) => Value::F32(value as f32),
+            Literal::F32(value) => Value::F32(value),
+            Literal::F16(value) => Value::F32(value.into()),
+            Literal::U32(value) => Value::U32(value),
+            Literal::U64(value) => Value::U32(value as u32),
+            Literal::I32(value) => Value::I32(value),
+            Literal::I64(value) | Literal::AbstractInt(value) => Value::I32(value as i32),
+            Literal::Bool(value) => Value::Bool(value),
+        }
+    }
+
+    pub fn as_f32(&self) -> Result<f32> {
+        match self {
+            Value::F32(value) => Ok(*value),
+            Value::I32(value) => Ok(*value as f32),
+            Value::U32(value) => Ok(*value as f32),
+            other => Err(format!("Expected a number, found {other:?}")),
+        }
+    }
+
+    pub fn as_bool(&self) -> Result<bool> {
+        match self {
+            Value::Bool(value) => Ok(*value),
+            other => Err(format!("Expected a bool, found {other:?}")),
+        }
+    }
+
+    pub fn as_index(&self) -> Result<usize> {
+        match self {
+            // negative indices are clamped, the same as out of bounds ones
+            Value::I32(value) => Ok((*value).max(0) as usize),
+            Value::U32(value) => Ok(*value as usize),
+            other => Err(format!("Expected an index, found {other:?}")),
+        }
+    }
+
+    pub fn into_pointer(self) -> Result<Pointer> {
+        match self {
+            Value::Pointer(pointer) => Ok(pointer),
+            other => Err(format!("Expected a pointer, found {other:?}")),
+        }
+    }
+
+    pub fn components(&self) -> Result<&[Value]> {
+        match self {
+            Value::Composite(components) => Ok(components),
+            other => Err(format!("Expected a vector or a composite, found {other:?}")),
+        }
+    }
+
+    /// Components of a float vector, a scalar is a single component
+    pub fn floats(&self) -> Result<Vec<f32>> {
+        match self {
+            Value::Composite(components) => components.iter().map(Value::as_f32).collect(),
+            scalar => Ok(vec![scalar.as_f32()?]),
+        }
+    }
+
+    pub fn from_floats(floats: &[f32]) -> Value {
+        Value::Composite(floats.iter().copied().map(Value::F32).collect())
+    }
+
+    /// Component of a composite, out of bounds indices are clamped
+    pub fn component(&self, index: usize) -> Result<Value> {
+        let components = self.components()?;
+        let last = components.len().saturating_sub(1);
+        components
+            .get(index.min(last))
+            .cloned()
+            .ok_or_else(|| "Access into an empty composite".to_owned())
+    }
+
+    /// Mutable component at `path`, out of bounds indices are clamped
+    pub fn at_path_mut(&mut self, path: &[usize]) -> Result<&mut Value> {
+        path.iter().try_fold(self, |value, &index| match value {
+            Value::Composite(components) => {
+                let last = components.len().saturating_sub(1);
+                components
+                    .get_mut(index.min(last))
+                    .ok_or_else(|| "Access into an empty composite".to_owned())
+            }
+            other => Err(format!("Can't access a component of {other:?}")),
+        })
+    }
+
+    pub fn is_matrix(&self) -> bool {
+        matches!(self, Value::Composite(columns) if matches!(columns.first(), Some(Value::Composite(_))))
+    }
+
+    /// Applies `f` to every scalar
+    pub fn map(&self, f: &mut dyn FnMut(&Value) -> Result<Value>) -> Result<Value> {
+        match self {
+            Value::Composite(components) => Ok(Value::Composite(
+                components
+                    .iter()
+                    .map(|component| component.map(f))
+                    .collect::<Result<_>>()?,
+            )),
+            scalar => f(scalar),
+        }
+    }
+
+    /// Applies `f` to the matching scalars, a scalar is paired with every component
+    pub fn zip(
+        &self,
+        other: &Value,
+        f: &mut dyn FnMut(&Value, &Value) -> Result<Value>,
+    ) -> Result<Value> {
+        match (self, other) {
+            (Value::Composite(a), Value::Composite(b)) => {
+                if a.len() != b.len() {
+                    return Err(format!("Mismatched sizes {} and {}", a.len(), b.len()));
+                }
+                Ok(Value::Composite(
+                    a.iter()
+                        .zip(b)
+                        .map(|(a, b)| a.zip(b, f))
+                        .collect::<Result<_>>()?,
+                ))
+            }
+            (Value::Composite(a), b) => Ok(Value::Composite(
+                a.iter().map(|a| a.zip(b, f)).collect::<Result<_>>()?,
+            )),
+            (a, Value::Composite(b)) => Ok(Value::Composite(
+                b.iter().map(|b| a.zip(b, f)).collect::<Result<_>>()?,
+            )),
+            (a, b) => f(a, b),
+        }
+    }
+
+    /// [`Value::zip`] of three values
+    pub fn zip3(
+        &self,
+        second: &Value,
+        third: &Value,
+        f: &mut dyn FnMut(&Value, &Value, &Value) -> Result<Value>,
+    ) -> Result<Value> {
+        let size = [self, second, third]
+            .into_iter()
+            .find_map(|value| match value {
+                Value::Composite(components) => Some(components.len()),
+                _ => None,
+            });
+        let Some(size) = size else {
+            return f(self, second, third);
+        };
+        let component = |value: &Value, index: usize| match value {
+            Value::Composite(_) => value.component(index),
+            scalar => Ok(scalar.clone()),
+        };
+        (0..size)
+            .map(|index| {
+                component(self, index)?.zip3(
+                    &component(second, index)?,
+                    &component(third, index)?,
+                    f,
+                )
+            })
+            .collect::<Result<_>>()
+            .map(Value::Composite)
+    }
+
+    pub fn unary(&self, op: UnaryOperator) -> Result<Value> {
+        self.map(&mut |value| {
+            Ok(match (op, value) {
+                (UnaryOperator::Negate, Value::F32(value)) => Value::F32(-value),
+                (UnaryOperator::Negate, Value::I32(value)) => Value::I32(value.wrapping_neg()),
+                (UnaryOperator::LogicalNot, Value::Bool(value)) => Value::Bool(!value),
+                (UnaryOperator::BitwiseNot, Value::I32(value)) => Value::I32(!value),
+                (UnaryOperator::BitwiseNot, Value::U32(value)) => Value::U32(!value),
+                (op, value) => return Err(format!("Can't apply {op:?} to {value:?}")),
+            })
+        })
+    }
+
+    /// Component-wise operators and the linear algebra products
+    pub fn binary(&self, op: BinaryOperator, other: &Value) -> Result<Value> {
+        if op == BinaryOperator::Multiply {
+            match (self.is_matrix(), other.is_matrix()) {
+                (true, true) => {
+                    let columns = other
+                        .components()?
+                        .iter()
+                        .map(|column| self.matrix_times_vector(column))
+                        .collect::<Result<_>>()?;
+                    return Ok(Value::Composite(columns));
+                }
+                (true, false) if matches!(other, Value::Composite(_)) => {
+                    return self.matrix_times_vector(other);
+                }
+                (false, true) if matches!(self, Value::Composite(_)) => {
+                    let products = other
+                        .components()?
+                        .iter()
+                        .map(|column| dot(self, column))
+                        .collect::<Result<_>>()?;
+                    return Ok(Value::Composite(products));
+                }
+                _ => (),
+            }
+        }
+        self.zip(other, &mut |a, b| scalar_binary(op, a, b))
+    }
+
+    fn matrix_times_vector(&self, vector: &Value) -> Result<Value> {
+        let columns = self.components()?;
+        let components = vector.components()?;
+        let mut sum: Option<Value> = None;
+        for (column, component) in columns.iter().zip(components) {
+            let product = column.binary(BinaryOperator::Multiply, component)?;
+            sum = Some(match sum {
+                Some(sum) => sum.binary(BinaryOperator::Add, &product)?,
+                None => product,
+            });
+        }
+        sum.ok_or_else(|| "Empty matrix".to_owned())
+    }
+
+    /// Converts every scalar to `kind`, `convert` is `false` for bitcasts
+    pub fn cast(&self, kind: ScalarKind, convert: bool) -> Result<Value> {
+        self.map(&mut |value| {
+            let kind = match kind {
+                ScalarKind::AbstractInt => ScalarKind::Sint,
+                ScalarKind::AbstractFloat => ScalarKind::Float,
+                kind => kind,
+            };
+            Ok(match (value, kind, convert) {
+                (Value::Bool(value), ScalarKind::Float, true) => {
+                    Value::F32(f32::from(u8::from(*value)))
+                }
+                (Value::Bool(value), ScalarKind::Sint, true) => Value::I32(i32::from(*value)),
+                (Value::Bool(value), ScalarKind::Uint, true) => Value::U32(u32::from(*value)),
+                (Value::F32(value), ScalarKind::Float, _) => Value::F32(*value),
+                // float to integer conversions saturate, the same as in WGSL
+                (Value::F32(value), ScalarKind::Sint, true) => Value::I32(*value as i32),
+                (Value::F32(value), ScalarKind::Uint, true) => Value::U32(*value as u32),
+                (Value::F32(value), ScalarKind::Bool, true) => Value::Bool(*value != 0.0),
+                (Value::F32(value), ScalarKind::Sint, false) => Value::I32(value.to_bits() as i32),
+                (Value::F32(value), ScalarKind::Uint, false) => Value::U32(value.to_bits()),
+                (Value::I32(value), ScalarKind::Float, true) => Value::F32(*value as f32),
+                (Value::I32(value), ScalarKind::Float, false) => {
+                    Value::F32(f32::from_bits(*value as u32))
+                }
+                (Value::I32(value), ScalarKind::Sint, _) => Value::I32(*value),
+                (Value::I32(value), ScalarKind::Uint, _) => Value::U32(*value as u32),
+                (Value::I32(value), ScalarKind::Bool, true) => Value::Bool(*value != 0),
+                (Value::U32(value), ScalarKind::Float, true) => Value::F32(*value as f32),
+                (Value::U32(value), ScalarKind::Float, false) => Value::F32(f32::from_bits(*value)),
+                (Value::U32(value), ScalarKind::Sint, _) => Value::I32(*value as i32),
+                (Value::U32(value), ScalarKind::Uint, _) => Value::U32(*value),
+                (Value::U32(value), ScalarKind::Bool, true) => Value::Bool(*value != 0),
+                (Value::Bool(value), ScalarKind::Bool, _) => Value::Bool(*value),
+                (value, kind, _) => return Err(format!("Can't convert {value:?} to {kind:?}")),
+            })
+        })
+    }
+}
+
+/// Sum of the component-wise products
+pub fn dot(a: &Value, b: &Value) -> Result<Value> {
+    let products = a.binary(BinaryOperator::Multiply, b)?;
+    let mut components = products.components()?.iter();
+    let first = components
+        .next()
+        .cloned()
+        .ok_or_else(|| "Dot product of empty vectors".to_owned())?;
+    components.try_fold(first, |sum, product| {
+        scalar_binary(BinaryOperator::Add, &sum, product)
+    })
+}
+
+fn scalar_binary(op: BinaryOperator, a: &Value, b: &Value) -> Result<Value> {
+    use BinaryOperator as Op;
+    Ok(match (a, b) {
+        (Value::F32(a), Value::F32(b)) => match op {
+            Op::Add => Value::F32(a + b),
+            Op::Subtract => Value::F32(a - b),
+            Op::Multiply => Value::F32(a * b),
+            Op::Divide => Value::F32(a / b),
+            // truncated remainder, the same as `%` in WGSL
+            Op::Modulo => Value::F32(a % b),
+            op => return compare(op, a, b),
+        },
+        (Value::I32(a), Value::I32(b)) => match op {
+            Op::Add => Value::I32(a.wrapping_add(*b)),
+            Op::Subtract => Value::I32(a.wrapping_sub(*b)),
+            Op::Multiply => Value::I32(a.wrapping_mul(*b)),
+            // division by zero returns the dividend in WGSL
+            Op::Divide if *b == 0 => Value::I32(*a),
+            Op::Divide => Value::I32(a.wrapping_div(*b)),
+            Op::Modulo if *b == 0 => Value::I32(0),
+            Op::Modulo => Value::I32(a.wrapping_rem(*b)),
+            Op::And => Value::I32(a & b),
+            Op::InclusiveOr => Value::I32(a | b),
+            Op::ExclusiveOr => Value::I32(a ^ b),
+            op => return compare(op, a, b),
+        },
+        (Value::U32(a), Value::U32(b)) => match op {
+            Op::Add => Value::U32(a.wrapping_add(*b)),
+            Op::Subtract => Value::U32(a.wrapping_sub(*b)),
+            Op::Multiply => Value::U32(a.wrapping_mul(*b)),
+            Op::Divide if *b == 0 => Value::U32(*a),
+            Op::Divide => Value::U32(a / b),
+            Op::Modulo if *b == 0 => Value::U32(0),
+            Op::Modulo => Value::U32(a % b),
+            Op::And => Value::U32(a & b),
+            Op::InclusiveOr => Value::U32(a | b),
+            Op::ExclusiveOr => Value::U32(a ^ b),
+            Op::ShiftLeft => Value::U32(a.wrapping_shl(*b)),
+            Op::ShiftRight => Value::U32(a.wrapping_shr(*b)),
+            op => return compare(op, a, b),
+        },
+        (Value::I32(a), Value::U32(b)) => match op {
+            Op::ShiftLeft => Value::I32(a.wrapping_shl(*b)),
+            Op::ShiftRight => Value::I32(a.wrapping_shr(*b)),
+            op => return Err(format!("Can't apply {op:?} to i32 and u32")),
+        },
+        (Value::Bool(a), Value::Bool(b)) => match op {
+            Op::LogicalAnd | Op::And => Value::Bool(*a && *b),
+            Op::LogicalOr | Op::InclusiveOr => Value::Bool(*a || *b),
+            Op::ExclusiveOr => Value::Bool(a ^ b),
+            op => return compare(op, a, b),
+        },
+        (a, b) => return Err(format!("Can't apply {op:?} to {a:?} and {b:?}")),
+    })
+}
+
+fn compare<T: PartialOrd>(op: BinaryOperator, a: &T, b: &T) -> Result<Value> {
+    Ok(Value::Bool(match op {
+        BinaryOperator::Equal => a == b,
+        BinaryOperator::NotEqual => a != b,
+        BinaryOperator::Less => a < b,
+        BinaryOperator::LessEqual => a <= b,
+        BinaryOperator::Greater => a > b,
+        BinaryOperator::GreaterEqual => a >= b,
+        op => return Err(format!("Unsupported operator {op:?}")),
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn modulo_by_zero() {
+        let modulo = |a, b| scalar_binary(BinaryOperator::Modulo, &a, &b);
+        assert_eq!(modulo(Value::I32(7), Value::I32(0)), Ok(Value::I32(0)));
+        assert_eq!(modulo(Value::I32(-7), Value::I32(0)), Ok(Value::I32(0)));
+        assert_eq!(modulo(Value::U32(7), Value::U32(0)), Ok(Value::U32(0)));
+        // the remainder is truncated, with the sign of the dividend
+        assert_eq!(modulo(Value::I32(-7), Value::I32(3)), Ok(Value::I32(-1)));
+        assert_eq!(
+            modulo(Value::I32(i32::MIN), Value::I32(-1)),
+            Ok(Value::I32(0))
+        );
+    }
+}
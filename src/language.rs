@@ -0,0 +1,105 @@
+use std::path::Path;
+
+use naga::ShaderStage;
+
+use crate::translation;
+
+/// File extensions of the shaders, WGSL, GLSL of a single stage and SPIR-V
+pub const EXTENSIONS: [&str; 5] = ["wgsl", "vert", "frag", "comp", "spv"];
+
+/// Shading language of a shader file, picked by the extension
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Language {
+    #[default]
+    Wgsl,
+    /// GLSL with the `main` entry point of the stage
+    Glsl(ShaderStage),
+    /// Binary module, shown as the disassembly
+    SpirV,
+}
+
+impl Language {
+    /// Files with unknown extensions are read as WGSL
+    pub fn from_path(path: &Path) -> Self {
+        let extension = path
+            .extension()
+            .map(|extension| extension.to_string_lossy().to_ascii_lowercase());
+        match extension.as_deref() {
+            Some("vert") => Language::Glsl(ShaderStage::Vertex),
+            Some("frag") => Language::Glsl(ShaderStage::Fragment),
+            Some("comp") => Language::Glsl(ShaderStage::Compute),
+            Some("spv") => Language::SpirV,
+            _ => Language::Wgsl,
+        }
+    }
+
+    /// Name of the syntax used by the highlighter
+    pub fn token(self) -> &'static str {
+        match self {
+            Language::Wgsl => "wgsl",
+            Language::Glsl(_) => "glsl",
+            Language::SpirV => "spv",
+        }
+    }
+}
+
+/// Source of a shader in one of the languages
+#[derive(Debug, Clone)]
+pub enum Shader {
+    Wgsl(String),
+    Glsl(ShaderStage, String),
+    SpirV(Vec<u8>),
+}
+
+impl Shader {
+    /// Reads the file in the language of its extension
+    pub fn read(path: &Path) -> Result<Self, String> {
+        let error = |e| format!("Error reading {}: {e}", path.display());
+        match Language::from_path(path) {
+            Language::SpirV => std::fs::read(path).map(Shader::SpirV).map_err(error),
+            language => std::fs::read_to_string(path)
+                .map(|text| Shader::new(language, text))
+                .map_err(error),
+        }
+    }
+
+    /// Shader of a text language, SPIR-V text is not a valid module
+    pub fn new(language: Language, text: String) -> Self {
+        match language {
+            Language::Wgsl => Shader::Wgsl(text),
+            Language::Glsl(stage) => Shader::Glsl(stage, text),
+            Language::SpirV => Shader::SpirV(text.into_bytes()),
+        }
+    }
+
+    pub fn language(&self) -> Language {
+        match self {
+            Shader::Wgsl(_) => Language::Wgsl,
+            Shader::Glsl(stage, _) => Language::Glsl(*stage),
+            Shader::SpirV(_) => Language::SpirV,
+        }
+    }
+
+    /// Source code, empty for SPIR-V
+    pub fn source(&self) -> &str {
+        match self {
+            Shader::Wgsl(text) | Shader::Glsl(_, text) => text,
+            Shader::SpirV(_) => "",
+        }
+    }
+
+    /// Text shown in the editor, the disassembly for SPIR-V
+    pub fn text(&self) -> String {
+        match self {
+            Shader::Wgsl(text) | Shader::Glsl(_, text) => text.clone(),
+            Shader::SpirV(bytes) => translation::disassemble(bytes),
+        }
+    }
+}
+
+/// The file has the extension of one of the languages
+pub fn is_shader(path: &Path) -> bool {
+    path.extension()
+        .and_then(|extension| extension.to_str())
+        .is_some_and(|extension| EXTENSIONS.contains(&extension.to_ascii_lowercase().as_str()))
+}
@@ -1,36 +1,99 @@
-use std::sync::Arc;
-use std::time::Instant;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 
+use clap::Parser;
 use iced_wgpu::graphics::{Shell, Viewport};
 use iced_wgpu::{Engine, Renderer, wgpu};
 use iced_winit::core::window;
-use iced_winit::core::{Event, Font, Pixels, Size, Theme, mouse, renderer};
+use iced_winit::core::{Event, Font, Pixels, Size, clipboard, mouse, renderer};
 use iced_winit::runtime::user_interface::{self, UserInterface};
 use iced_winit::{Clipboard, conversion, winit};
-use wgpu_types::TextureFormat;
 use winit::application::ApplicationHandler;
-use winit::event::{ElementState, KeyEvent, WindowEvent};
-use winit::event_loop::{ActiveEventLoop, EventLoop};
+use winit::dpi::PhysicalSize;
+use winit::event::{ElementState, KeyEvent, MouseButton, WindowEvent};
+use winit::event_loop::{ActiveEventLoop, EventLoop, EventLoopProxy};
 use winit::keyboard::{KeyCode, ModifiersState, PhysicalKey};
 use winit::window::Window;
 
+mod batch;
+mod cli;
+mod clock;
 mod controls;
+mod diagnostic;
+mod document;
 mod editor;
+mod entry_points;
+mod file_browser;
+mod golden;
+mod headless;
 mod highlighter;
+mod interpreter;
+mod language;
+mod pad;
+mod params;
+mod prelude;
+mod recorder;
 mod scene;
+mod shadertoy;
+mod translation;
 mod validator;
 mod watch;
 
+use clock::Tick;
 use controls::{Controls, Message};
-use editor::Editor;
+use diagnostic::Diagnostic;
+use editor::{Capture, Editor};
+use entry_points::Selection;
+use language::Shader;
+use recorder::{Recorder, Recording};
+use scene::channels::Channel;
+use scene::compute::Dispatch;
+use scene::globals::Globals;
+use scene::graph::Pass;
+use validator::Validation;
 
 const SHADER_FILE: &str = "./shaders/pattern_2.wgsl";
-const SHADER_SOURCE: &str = include_str!("../shaders/pattern_2.wgsl");
+const DEFAULT_WIDTH: u32 = 800;
+const DEFAULT_HEIGHT: u32 = 600;
+/// Initial content of a new shader
+const SHADER_TEMPLATE: &str = include_str!("../shaders/gradient.wgsl");
+/// Time spent recording per event loop iteration
+const RECORD_CHUNK: Duration = Duration::from_millis(50);
 
 #[derive(Debug)]
 pub enum CustomEvent {
     ShaderFileChanged,
-    UpdateShader(String),
+    /// Shader of a pass of the render graph with the selected entry points,
+    /// and its background validation if it's still current
+    UpdateShader(Pass, Shader, Selection, Option<Arc<Validation>>),
+    /// Pass left without a shader
+    RemovePass(Pass),
+    /// Shader file opened in the editor, `None` for a new unsaved shader
+    WatchFile(Option<PathBuf>),
+    /// Result of the background validation of a document revision
+    Validated {
+        document: u64,
+        revision: u64,
+        result: Result<Arc<Validation>, Diagnostic>,
+    },
+    /// wgpu error raised outside of an error scope
+    GpuError(String),
+    /// Input channel settings changed in the UI
+    UpdateChannel(Pass, usize, Channel),
+    /// Workgroup counts of the compute pass changed in the UI
+    SetDispatch(Dispatch),
+    /// Render a frame offscreen and save it as a PNG
+    ExportImage {
+        path: PathBuf,
+        width: u32,
+        height: u32,
+        tick: Tick,
+    },
+    /// Render an animation offscreen at a fixed timestep
+    Record(Recording),
+    /// Text copied with a button, like the translated shader
+    CopyToClipboard(String),
 }
 
 struct App {
@@ -41,10 +104,21 @@ struct App {
     events: Vec<Event>,
     cache: user_interface::Cache,
     controls: Controls,
+    event_loop_proxy: EventLoopProxy<CustomEvent>,
+    globals: GlobalsState,
+    args: cli::Args,
+    shader_path: Option<PathBuf>,
+    watcher: Option<watch::FileWatcher>,
+    recording: Option<ActiveRecording>,
 }
 
 impl App {
-    fn new(controls: Controls) -> App {
+    fn new(
+        controls: Controls,
+        event_loop_proxy: EventLoopProxy<CustomEvent>,
+        args: cli::Args,
+        watcher: Option<watch::FileWatcher>,
+    ) -> App {
         let modifiers = ModifiersState::default();
         App {
             app_data: None,
@@ -54,8 +128,157 @@ impl App {
             events: Vec::new(),
             cache: user_interface::Cache::new(),
             controls,
+            event_loop_proxy,
+            globals: GlobalsState::new(),
+            shader_path: Some(args.shader.clone()),
+            args,
+            watcher,
+            recording: None,
         }
     }
+
+    /// Renders the next frames of the recording for up to [`RECORD_CHUNK`],
+    /// finishes it after the last one
+    fn record_frames(&mut self) {
+        let (Some(app_data), Some(active)) = (&mut self.app_data, &mut self.recording) else {
+            return;
+        };
+        let started = Instant::now();
+        let mut result = Ok(());
+        while active.frame < active.recording.frames && started.elapsed() < RECORD_CHUNK {
+            let globals = active.recording.globals(active.start, active.frame);
+            result = app_data
+                .editor
+                .capture(
+                    &app_data.device,
+                    &app_data.queue,
+                    &mut active.capture,
+                    &globals,
+                )
+                .and_then(|image| active.recorder.add_frame(&image));
+            if result.is_err() {
+                break;
+            }
+            active.frame += 1;
+        }
+        app_data.window.request_redraw();
+        if result.is_ok() && active.frame < active.recording.frames {
+            self.controls.update(Message::RecordProgress(Some([
+                active.frame,
+                active.recording.frames,
+            ])));
+            return;
+        }
+
+        let Some(active) = self.recording.take() else {
+            return;
+        };
+        self.controls.update(Message::RecordProgress(None));
+        match result.and_then(|()| active.recorder.finish()) {
+            Ok(warnings) => self
+                .controls
+                .update(Message::Recorded(active.recording.path, warnings)),
+            Err(e) => self.controls.update(Message::FileError(e)),
+        }
+    }
+
+    fn update_shader(
+        &mut self,
+        pass: Pass,
+        shader: &Shader,
+        selection: &Selection,
+        validation: Option<Arc<Validation>>,
+    ) {
+        let Some(app_data) = &mut self.app_data else {
+            return;
+        };
+        // files changed on disk didn't go through the background validator
+        let validation =
+            match validation.map_or_else(|| validator::validate(shader).map(Arc::new), Ok) {
+                Ok(validation) => validation,
+                Err(e) => {
+                    self.controls.update(Message::ShaderError(e));
+                    app_data.window.request_redraw();
+                    return;
+                }
+            };
+        let selection = validation.entry_points.resolve(selection);
+        match app_data.editor.update_shader(
+            &app_data.device,
+            &app_data.config,
+            pass,
+            shader,
+            &validation,
+            &selection,
+        ) {
+            Ok(()) => {
+                self.controls
+                    .update(Message::ShaderValid(validation.entry_points.clone()));
+                self.controls
+                    .update(Message::SetParams(pass, validation.params.clone()));
+            }
+            Err(e) => self
+                .controls
+                .update(Message::ShaderError(Diagnostic::error(e))),
+        }
+        app_data.window.request_redraw();
+    }
+}
+
+/// Recording in progress, rendered a few frames per event loop iteration
+/// so the window stays responsive
+struct ActiveRecording {
+    recording: Recording,
+    recorder: Recorder,
+    capture: Capture,
+    start: Globals,
+    /// Next frame to render
+    frame: u32,
+}
+
+/// Values of the built-in uniforms carried between frames
+struct GlobalsState {
+    mouse_position: [f32; 2],
+    mouse: [f32; 4],
+}
+
+impl GlobalsState {
+    fn new() -> Self {
+        GlobalsState {
+            mouse_position: [0.0; 2],
+            mouse: [0.0; 4],
+        }
+    }
+
+    fn cursor_moved(&mut self, x: f32, y: f32) {
+        self.mouse_position = [x, y];
+        if self.mouse[2] > 0.0 {
+            self.mouse[0] = x;
+            self.mouse[1] = y;
+        }
+    }
+
+    fn mouse_input(&mut self, state: ElementState) {
+        let [x, y] = self.mouse_position;
+        match state {
+            ElementState::Pressed => self.mouse = [x, y, x, y],
+            ElementState::Released => {
+                self.mouse[2] = -self.mouse[2].abs();
+                self.mouse[3] = -self.mouse[3].abs();
+            }
+        }
+    }
+
+    fn globals(&self, width: u32, height: u32, tick: Tick) -> Globals {
+        let mut globals = Globals::default();
+        globals.resolution = [width as f32, height as f32];
+        globals.time = tick.time;
+        globals.time_delta = tick.time_delta;
+        globals.mouse = self.mouse;
+        globals.frame = tick.frame;
+        globals.set_date_now();
+        globals
+    }
 }
 
 struct AppData {
@@ -67,7 +290,6 @@ struct AppData {
     adapter: wgpu::Adapter,
     device: wgpu::Device,
     queue: wgpu::Queue,
-    format: TextureFormat,
     config: wgpu::SurfaceConfiguration,
     editor: Editor,
     renderer: Renderer,
@@ -79,38 +301,201 @@ impl ApplicationHandler<CustomEvent> for App {
             println!("Already initialized, skipping");
             return;
         }
-        let app_data = init_app(event_loop);
+        let (app_data, startup) = init_app(
+            event_loop,
+            self.event_loop_proxy.clone(),
+            &self.args,
+            self.shader_path.as_deref(),
+        );
         self.app_data = Some(app_data);
+
+        // the initial shader is rendered by `Editor::init`, the template if it failed
+        match startup {
+            Some(Ok(validation)) => {
+                self.controls
+                    .update(Message::ShaderValid(validation.entry_points));
+                self.controls
+                    .update(Message::SetParams(Pass::Image, validation.params));
+            }
+            Some(Err(diagnostic)) => self.controls.update(Message::ShaderError(diagnostic)),
+            None => (),
+        }
     }
 
     fn user_event(&mut self, _event_loop: &ActiveEventLoop, event: CustomEvent) {
-        let Some(app_data) = &mut self.app_data else {
-            return;
-        };
         match event {
             CustomEvent::ShaderFileChanged => {
-                app_data
-                    .editor
-                    .update_rectangle_shader(&app_data.device, &app_data.config);
-                app_data.window.request_redraw();
+                let Some(path) = &self.shader_path else {
+                    return;
+                };
+                // keep the previous shader if the file is gone or being replaced
+                match Shader::read(path) {
+                    Ok(shader) => {
+                        let (pass, selection) = self.controls.active_shader();
+                        self.update_shader(pass, &shader, &selection, None)
+                    }
+                    Err(message) => {
+                        self.controls
+                            .update(Message::ShaderError(Diagnostic::error(message)));
+                        if let Some(app_data) = &self.app_data {
+                            app_data.window.request_redraw();
+                        }
+                    }
+                }
             }
-            CustomEvent::UpdateShader(text) => {
-                if let Err(e) = validator::validate(&text) {
-                    self.controls.update(Message::ShaderError(e));
+            CustomEvent::UpdateShader(pass, shader, selection, validation) => {
+                self.update_shader(pass, &shader, &selection, validation);
+            }
+            CustomEvent::RemovePass(pass) => {
+                if let Some(app_data) = &mut self.app_data {
+                    app_data
+                        .editor
+                        .remove_pass(&app_data.device, &app_data.config, pass);
+                    app_data.window.request_redraw();
+                }
+            }
+            CustomEvent::WatchFile(path) => {
+                if let Some(watcher) = &mut self.watcher {
+                    match &path {
+                        Some(path) => {
+                            if let Err(e) = watcher.watch(path) {
+                                self.controls.update(Message::FileError(e));
+                            }
+                        }
+                        None => watcher.unwatch(),
+                    }
+                }
+                self.shader_path = path;
+            }
+            CustomEvent::Validated {
+                document,
+                revision,
+                result,
+            } => {
+                self.controls.update(Message::Validated {
+                    document,
+                    revision,
+                    result,
+                });
+                if let Some(app_data) = &self.app_data {
                     app_data.window.request_redraw();
-                    return;
-                } else {
-                    self.controls.update(Message::ShaderValid);
                 }
-                app_data.editor.update_rectangle_shader_with_text(
+            }
+            CustomEvent::SetDispatch(dispatch) => {
+                if let Some(app_data) = &mut self.app_data {
+                    app_data.editor.set_dispatch(dispatch);
+                }
+            }
+            CustomEvent::UpdateChannel(pass, index, channel) => {
+                let Some(app_data) = &mut self.app_data else {
+                    return;
+                };
+                let result = app_data.editor.update_channel(
                     &app_data.device,
-                    &app_data.config,
-                    &text,
+                    &app_data.queue,
+                    pass,
+                    index,
+                    &channel,
                 );
+                match result {
+                    Ok(()) => self
+                        .controls
+                        .update(Message::ChannelUpdated(pass, index, channel)),
+                    Err(e) => self.controls.update(Message::FileError(e)),
+                }
+                app_data.window.request_redraw();
+            }
+            CustomEvent::ExportImage {
+                path,
+                width,
+                height,
+                tick,
+            } => {
+                let Some(app_data) = &mut self.app_data else {
+                    return;
+                };
+                let globals = self.globals.globals(width, height, tick);
+                let result = app_data
+                    .editor
+                    .export(
+                        &app_data.device,
+                        &app_data.queue,
+                        &app_data.config,
+                        &globals,
+                    )
+                    .and_then(|image| {
+                        image
+                            .save_with_format(&path, image::ImageFormat::Png)
+                            .map_err(|e| format!("Error writing {}: {e}", path.display()))
+                    });
+                match result {
+                    Ok(()) => self.controls.update(Message::Exported(path)),
+                    Err(e) => self.controls.update(Message::FileError(e)),
+                }
+                app_data.window.request_redraw();
+            }
+            CustomEvent::Record(recording) => {
+                let Some(app_data) = &mut self.app_data else {
+                    return;
+                };
+                if self.recording.is_some() {
+                    return;
+                }
+                let start =
+                    self.globals
+                        .globals(recording.width, recording.height, Tick::default());
+                let result = app_data
+                    .editor
+                    .start_capture(
+                        &app_data.device,
+                        &app_data.config,
+                        recording.width,
+                        recording.height,
+                    )
+                    .and_then(|capture| {
+                        Ok(ActiveRecording {
+                            recorder: Recorder::new(&recording)?,
+                            recording,
+                            capture,
+                            start,
+                            frame: 0,
+                        })
+                    });
+                match result {
+                    Ok(active) => {
+                        self.controls
+                            .update(Message::RecordProgress(Some([0, active.recording.frames])));
+                        self.recording = Some(active);
+                    }
+                    Err(e) => self.controls.update(Message::FileError(e)),
+                }
                 app_data.window.request_redraw();
             }
+            CustomEvent::CopyToClipboard(text) => {
+                if let Some(app_data) = &mut self.app_data {
+                    app_data.clipboard.write(clipboard::Kind::Standard, text);
+                }
+            }
+            CustomEvent::GpuError(message) => {
+                self.controls
+                    .update(Message::ShaderError(Diagnostic::error(message)));
+                if let Some(app_data) = &self.app_data {
+                    app_data.window.request_redraw();
+                }
+            }
+        }
+    }
+
+    fn about_to_wait(&mut self, _event_loop: &ActiveEventLoop) {
+        self.record_frames();
+        let Some(app_data) = &self.app_data else {
+            return;
+        };
+        if self.controls.is_playing() {
+            app_data.window.request_redraw();
         }
     }
+
     fn window_event(
         &mut self,
         event_loop: &ActiveEventLoop,
@@ -126,7 +511,7 @@ impl ApplicationHandler<CustomEvent> for App {
             surface,
             device,
             queue,
-            format,
+            config,
             editor,
             renderer,
             ..
@@ -138,6 +523,15 @@ impl ApplicationHandler<CustomEvent> for App {
                     position,
                     app_data.viewport.scale_factor(),
                 ));
+                self.globals
+                    .cursor_moved(position.x as f32, position.y as f32);
+            }
+            WindowEvent::MouseInput {
+                state,
+                button: MouseButton::Left,
+                ..
+            } => {
+                self.globals.mouse_input(state);
             }
             WindowEvent::ModifiersChanged(modifiers) => {
                 self.modifiers = modifiers.state();
@@ -154,12 +548,23 @@ impl ApplicationHandler<CustomEvent> for App {
                 PhysicalKey::Code(KeyCode::Escape) => {
                     event_loop.exit();
                 }
-                PhysicalKey::Code(KeyCode::KeyR) => {
-                    if self.modifiers.control_key() {
-                        self.controls.update(controls::Message::UpdateShader);
-                        return;
+                PhysicalKey::Code(KeyCode::KeyR) if self.modifiers.control_key() => {
+                    self.controls.update(controls::Message::UpdateShader);
+                    return;
+                }
+                PhysicalKey::Code(KeyCode::KeyS) if self.modifiers.control_key() => {
+                    if self.modifiers.shift_key() {
+                        self.controls.update(controls::Message::SaveAs);
+                    } else {
+                        self.controls.update(controls::Message::Save);
                     }
                 }
+                PhysicalKey::Code(KeyCode::KeyO) if self.modifiers.control_key() => {
+                    self.controls.update(controls::Message::Open);
+                }
+                PhysicalKey::Code(KeyCode::KeyE) if self.modifiers.control_key() => {
+                    self.controls.update(controls::Message::Export);
+                }
                 _ => (),
             },
             WindowEvent::Resized(_) => {
@@ -177,19 +582,10 @@ impl ApplicationHandler<CustomEvent> for App {
                         window.scale_factor() as f32,
                     );
 
-                    surface.configure(
-                        device,
-                        &wgpu::SurfaceConfiguration {
-                            format: *format,
-                            usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
-                            width: size.width,
-                            height: size.height,
-                            present_mode: wgpu::PresentMode::AutoVsync,
-                            alpha_mode: wgpu::CompositeAlphaMode::Auto,
-                            view_formats: vec![],
-                            desired_maximum_frame_latency: 2,
-                        },
-                    );
+                    config.width = size.width;
+                    config.height = size.height;
+                    surface.configure(device, config);
+                    editor.resize(device, config.width, config.height);
 
                     self.resized = false;
                 }
@@ -205,10 +601,16 @@ impl ApplicationHandler<CustomEvent> for App {
                             .texture
                             .create_view(&wgpu::TextureViewDescriptor::default());
 
-                        editor.render(&view, &mut encoder);
+                        let tick = self.controls.tick();
+                        let globals = self.globals.globals(config.width, config.height, tick);
+                        editor.update_globals(queue, &globals);
+                        editor.update_params(queue, &self.controls.params().bytes());
+                        editor.render(device, &view, &mut encoder);
 
                         queue.submit([encoder.finish()]);
 
+                        let operation = self.controls.take_operation();
+
                         let mut interface = UserInterface::build(
                             self.controls.view(),
                             app_data.viewport.logical_size(),
@@ -216,6 +618,10 @@ impl ApplicationHandler<CustomEvent> for App {
                             renderer,
                         );
 
+                        if let Some(mut operation) = operation {
+                            interface.operate(renderer, &mut operation);
+                        }
+
                         let (state, _) = interface.update(
                             &[Event::Window(
                                 window::Event::RedrawRequested(Instant::now()),
@@ -238,7 +644,7 @@ impl ApplicationHandler<CustomEvent> for App {
                             }
                         }
 
-                        let theme = Theme::SolarizedDark;
+                        let theme = self.args.theme.ui();
                         interface.draw(
                             renderer,
                             &theme,
@@ -303,9 +709,21 @@ impl ApplicationHandler<CustomEvent> for App {
     }
 }
 
-fn init_app(event_loop: &ActiveEventLoop) -> AppData {
+fn init_app(
+    event_loop: &ActiveEventLoop,
+    event_loop_proxy: EventLoopProxy<CustomEvent>,
+    args: &cli::Args,
+    shader_path: Option<&Path>,
+) -> (AppData, Option<Result<Validation, Diagnostic>>) {
+    let mut window_attributes = winit::window::WindowAttributes::default();
+    if args.width.is_some() || args.height.is_some() {
+        window_attributes = window_attributes.with_inner_size(PhysicalSize::new(
+            args.width.unwrap_or(DEFAULT_WIDTH),
+            args.height.unwrap_or(DEFAULT_HEIGHT),
+        ));
+    }
     let window = event_loop
-        .create_window(winit::window::WindowAttributes::default())
+        .create_window(window_attributes)
         .expect("Should create window");
 
     let window = Arc::new(window);
@@ -322,7 +740,7 @@ fn init_app(event_loop: &ActiveEventLoop) -> AppData {
         .create_surface(window.clone())
         .expect("Should create surface");
 
-    let (format, adapter, device, queue) = futures::executor::block_on(async {
+    let (format, present_mode, adapter, device, queue) = futures::executor::block_on(async {
         let adapter = wgpu::util::initialize_adapter_from_env_or_default(&instance, Some(&surface))
             .await
             .expect("Adapter not found");
@@ -331,7 +749,8 @@ fn init_app(event_loop: &ActiveEventLoop) -> AppData {
         println!("Using {} ({:?})", adapter_info.name, adapter_info.backend);
 
         let adapter_features = adapter.features();
-        let needed_limits = wgpu::Limits::default();
+        // allow exporting frames larger than the window
+        let needed_limits = wgpu::Limits::default().using_resolution(adapter.limits());
         let capabilities = surface.get_capabilities(&adapter);
 
         let format = capabilities
@@ -343,6 +762,18 @@ fn init_app(event_loop: &ActiveEventLoop) -> AppData {
             .or_else(|| capabilities.formats.first().copied())
             .expect("Format not found");
 
+        let present_mode = wgpu::PresentMode::from(args.present_mode);
+        let present_mode = if capabilities.present_modes.contains(&present_mode)
+            || matches!(
+                present_mode,
+                wgpu::PresentMode::AutoVsync | wgpu::PresentMode::AutoNoVsync
+            ) {
+            present_mode
+        } else {
+            println!("Present mode {present_mode:?} is not supported, using AutoVsync");
+            wgpu::PresentMode::AutoVsync
+        };
+
         let (device, queue) = adapter
             .request_device(&wgpu::DeviceDescriptor {
                 label: None,
@@ -355,15 +786,27 @@ fn init_app(event_loop: &ActiveEventLoop) -> AppData {
             .await
             .expect("Device not found");
 
-        (format, adapter, device, queue)
+        (format, present_mode, adapter, device, queue)
     });
 
+    // the default handler panics, report the error instead
+    let event_loop_proxy = Mutex::new(event_loop_proxy);
+    device.on_uncaptured_error(Arc::new(move |error| {
+        let event = CustomEvent::GpuError(error.to_string());
+        let sent = event_loop_proxy
+            .lock()
+            .map(|proxy| proxy.send_event(event).is_ok());
+        if !matches!(sent, Ok(true)) {
+            println!("Error sending custom winit event");
+        }
+    }));
+
     let mut config = wgpu::SurfaceConfiguration {
         usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
         format,
         width: physical_size.width,
         height: physical_size.height,
-        present_mode: wgpu::PresentMode::AutoVsync,
+        present_mode,
         alpha_mode: wgpu::CompositeAlphaMode::Auto,
         view_formats: vec![],
         desired_maximum_frame_latency: 2,
@@ -380,7 +823,7 @@ fn init_app(event_loop: &ActiveEventLoop) -> AppData {
     );
     let clipboard = Clipboard::connect(window.clone());
 
-    let editor = Editor::init(&config, &device);
+    let (editor, startup) = Editor::init(&config, &device, &queue, shader_path);
 
     let engine = Engine::new(
         &adapter,
@@ -392,7 +835,7 @@ fn init_app(event_loop: &ActiveEventLoop) -> AppData {
     );
     let renderer = Renderer::new(engine, Font::default(), Pixels(16.0));
 
-    AppData {
+    let app_data = AppData {
         window,
         viewport,
         clipboard,
@@ -400,25 +843,66 @@ fn init_app(event_loop: &ActiveEventLoop) -> AppData {
         adapter,
         device,
         queue,
-        format,
         config,
         editor,
         renderer,
-    }
+    };
+    (app_data, startup)
 }
 
 fn main() {
+    let args = cli::Args::parse();
+
+    if let Some(command) = &args.command {
+        // subcommands run without a window, `false` means some of the shaders failed
+        let result = match command {
+            cli::Command::Render(render_args) => headless::run(render_args).map(|()| true),
+            cli::Command::Validate(validate_args) => batch::run(validate_args),
+            cli::Command::Golden(golden_args) => golden::run(golden_args),
+            cli::Command::Import(import_args) => shadertoy::run(import_args),
+        };
+        match result {
+            Ok(true) => (),
+            Ok(false) => std::process::exit(1),
+            Err(e) => {
+                eprintln!("{e}");
+                std::process::exit(1);
+            }
+        }
+        return;
+    }
+
     let event_loop = EventLoop::with_user_event()
         .build()
         .expect("Should build event loop");
 
     let event_loop_proxy = event_loop.create_proxy();
-    let controls = Controls::new(event_loop_proxy.clone());
-
-    let mut app = App::new(controls);
+    let mut controls = Controls::new(
+        event_loop_proxy.clone(),
+        &args.shader,
+        !args.hide_editor,
+        args.theme.highlighter(),
+    );
 
     // watch for shader changes
-    watch::init(event_loop_proxy);
+    let watcher = if args.no_watch {
+        None
+    } else {
+        match watch::init(event_loop_proxy.clone()) {
+            Ok(mut watcher) => {
+                if let Err(e) = watcher.watch(&args.shader) {
+                    controls.update(Message::FileError(e));
+                }
+                Some(watcher)
+            }
+            Err(e) => {
+                controls.update(Message::FileError(e));
+                None
+            }
+        }
+    };
+
+    let mut app = App::new(controls, event_loop_proxy, args, watcher);
 
     println!("Entering render loop...");
     event_loop.run_app(&mut app).expect("Should run event loop");
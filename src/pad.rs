@@ -0,0 +1,212 @@
+use iced_winit::core::layout::{self, Layout};
+use iced_winit::core::widget::{Tree, Widget, tree};
+use iced_winit::core::{
+    Border, Clipboard, Element, Event, Length, Point, Rectangle, Shell, Size, Theme, mouse,
+    renderer, touch,
+};
+
+/// Square area picking a 2D value by dragging the handle,
+/// `y` grows upwards like in a plot
+pub struct Pad<'a, Message> {
+    min: f32,
+    max: f32,
+    value: [f32; 2],
+    on_change: Box<dyn Fn([f32; 2]) -> Message + 'a>,
+    size: f32,
+}
+
+#[derive(Default)]
+struct State {
+    is_dragging: bool,
+}
+
+const HANDLE_SIZE: f32 = 8.0;
+
+impl<'a, Message> Pad<'a, Message> {
+    pub fn new(
+        min: f32,
+        max: f32,
+        value: [f32; 2],
+        on_change: impl Fn([f32; 2]) -> Message + 'a,
+    ) -> Self {
+        Pad {
+            min,
+            max,
+            value,
+            on_change: Box::new(on_change),
+            size: 100.0,
+        }
+    }
+
+    /// Value at the cursor, clamped to the range
+    fn locate(&self, bounds: Rectangle, position: Point) -> [f32; 2] {
+        let x = ((position.x - bounds.x) / bounds.width).clamp(0.0, 1.0);
+        let y = 1.0 - ((position.y - bounds.y) / bounds.height).clamp(0.0, 1.0);
+        [x, y].map(|t| self.min + t * (self.max - self.min))
+    }
+}
+
+impl<Message, Renderer> Widget<Message, Theme, Renderer> for Pad<'_, Message>
+where
+    Renderer: renderer::Renderer,
+{
+    fn tag(&self) -> tree::Tag {
+        tree::Tag::of::<State>()
+    }
+
+    fn state(&self) -> tree::State {
+        tree::State::new(State::default())
+    }
+
+    fn size(&self) -> Size<Length> {
+        Size::new(Length::Fixed(self.size), Length::Fixed(self.size))
+    }
+
+    fn layout(
+        &mut self,
+        _tree: &mut Tree,
+        _renderer: &Renderer,
+        limits: &layout::Limits,
+    ) -> layout::Node {
+        layout::atomic(limits, self.size, self.size)
+    }
+
+    fn update(
+        &mut self,
+        tree: &mut Tree,
+        event: &Event,
+        layout: Layout<'_>,
+        cursor: mouse::Cursor,
+        _renderer: &Renderer,
+        _clipboard: &mut dyn Clipboard,
+        shell: &mut Shell<'_, Message>,
+        _viewport: &Rectangle,
+    ) {
+        let state = tree.state.downcast_mut::<State>();
+        let bounds = layout.bounds();
+        match event {
+            Event::Mouse(mouse::Event::ButtonPressed(mouse::Button::Left))
+            | Event::Touch(touch::Event::FingerPressed { .. }) => {
+                if let Some(position) = cursor.position_over(bounds) {
+                    state.is_dragging = true;
+                    shell.publish((self.on_change)(self.locate(bounds, position)));
+                    shell.capture_event();
+                }
+            }
+            Event::Mouse(mouse::Event::ButtonReleased(mouse::Button::Left))
+            | Event::Touch(touch::Event::FingerLifted { .. })
+            | Event::Touch(touch::Event::FingerLost { .. }) => {
+                state.is_dragging = false;
+            }
+            Event::Mouse(mouse::Event::CursorMoved { .. })
+            | Event::Touch(touch::Event::FingerMoved { .. }) => {
+                if state.is_dragging
+                    && let Some(position) = cursor.land().position()
+                {
+                    shell.publish((self.on_change)(self.locate(bounds, position)));
+                    shell.capture_event();
+                }
+            }
+            _ => (),
+        }
+    }
+
+    fn draw(
+        &self,
+        _tree: &Tree,
+        renderer: &mut Renderer,
+        theme: &Theme,
+        _style: &renderer::Style,
+        layout: Layout<'_>,
+        _cursor: mouse::Cursor,
+        _viewport: &Rectangle,
+    ) {
+        let bounds = layout.bounds();
+        let palette = theme.extended_palette();
+        renderer.fill_quad(
+            renderer::Quad {
+                bounds,
+                border: Border {
+                    color: palette.background.strong.color,
+                    width: 1.0,
+                    radius: 2.0.into(),
+                },
+                ..renderer::Quad::default()
+            },
+            palette.background.weak.color,
+        );
+
+        let [x, y] = self.value.map(|value| {
+            let range = self.max - self.min;
+            if range > 0.0 {
+                ((value - self.min) / range).clamp(0.0, 1.0)
+            } else {
+                0.0
+            }
+        });
+        let center = Point::new(
+            bounds.x + x * bounds.width,
+            bounds.y + (1.0 - y) * bounds.height,
+        );
+        // crosshair through the handle
+        let lines = [
+            Rectangle::new(
+                Point::new(bounds.x, center.y - 0.5),
+                Size::new(bounds.width, 1.0),
+            ),
+            Rectangle::new(
+                Point::new(center.x - 0.5, bounds.y),
+                Size::new(1.0, bounds.height),
+            ),
+        ];
+        for line in lines {
+            renderer.fill_quad(
+                renderer::Quad {
+                    bounds: line,
+                    ..renderer::Quad::default()
+                },
+                palette.background.strong.color,
+            );
+        }
+        renderer.fill_quad(
+            renderer::Quad {
+                bounds: Rectangle::new(
+                    Point::new(center.x - HANDLE_SIZE / 2.0, center.y - HANDLE_SIZE / 2.0),
+                    Size::new(HANDLE_SIZE, HANDLE_SIZE),
+                ),
+                border: Border {
+                    radius: (HANDLE_SIZE / 2.0).into(),
+                    ..Border::default()
+                },
+                ..renderer::Quad::default()
+            },
+            palette.primary.base.color,
+        );
+    }
+
+    fn mouse_interaction(
+        &self,
+        tree: &Tree,
+        layout: Layout<'_>,
+        cursor: mouse::Cursor,
+        _viewport: &Rectangle,
+        _renderer: &Renderer,
+    ) -> mouse::Interaction {
+        let state = tree.state.downcast_ref::<State>();
+        if state.is_dragging || cursor.is_over(layout.bounds()) {
+            mouse::Interaction::Crosshair
+        } else {
+            mouse::Interaction::default()
+        }
+    }
+}
+
+impl<'a, Message, Renderer> From<Pad<'a, Message>> for Element<'a, Message, Theme, Renderer>
+where
+    Message: 'a,
+    Renderer: renderer::Renderer + 'a,
+{
+    fn from(pad: Pad<'a, Message>) -> Self {
+        Element::new(pad)
+    }
+}
@@ -0,0 +1,362 @@
+//! Parameters of the shader, a uniform struct at `@group(0) @binding(1)`
+//! turned into controls by the comments on the lines of its members:
+//!
+//! ```wgsl
+//! struct Params {
+//!     speed: f32,      // @range(0, 10) @default(1.5)
+//!     tint: vec3<f32>, // @color @default(1, 0.5, 0.2)
+//! }
+//! @group(0) @binding(1) var<uniform> params: Params;
+//! ```
+
+use naga::{AddressSpace, Module, ScalarKind, TypeInner};
+
+pub const GROUP: u32 = 0;
+pub const BINDING: u32 = 1;
+/// Size of the uniform buffer, the largest struct of parameters
+pub const BUFFER_SIZE: u64 = 4096;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Scalar {
+    F32,
+    I32,
+    U32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Control {
+    /// A slider per component
+    Slider,
+    /// Integer scalar annotated with `@checkbox`, 0 or 1
+    Checkbox,
+    /// `vec3<f32>` or `vec4<f32>` annotated with `@color`, the components are 0 to 1
+    Color,
+    /// `vec2<f32>`, both components in the range
+    Pad,
+}
+
+/// A member of the struct with the control made from its annotations
+#[derive(Debug, Clone, PartialEq)]
+pub struct Param {
+    pub name: String,
+    pub scalar: Scalar,
+    /// 1 for scalars, 2 to 4 for vectors
+    pub components: usize,
+    /// Byte offset in the struct
+    pub offset: u32,
+    pub control: Control,
+    pub min: f32,
+    pub max: f32,
+    pub default: [f32; 4],
+}
+
+impl Param {
+    /// Values are kept across reloads when this doesn't change
+    fn same_type(&self, other: &Param) -> bool {
+        self.name == other.name
+            && self.scalar == other.scalar
+            && self.components == other.components
+    }
+
+    /// Reads the same bytes of the buffer as `other`
+    pub fn same_member(&self, other: &Param) -> bool {
+        self.same_type(other) && self.offset == other.offset
+    }
+
+    /// Integers move in whole steps
+    pub fn step(&self) -> f32 {
+        match self.scalar {
+            Scalar::F32 => (self.max - self.min) / 1000.0,
+            Scalar::I32 | Scalar::U32 => 1.0,
+        }
+    }
+
+    fn clamp(&self, mut value: [f32; 4]) -> [f32; 4] {
+        for component in &mut value[..self.components] {
+            *component = component.clamp(self.min, self.max);
+        }
+        value
+    }
+}
+
+/// Parameters of the rendered shader and their current values
+#[derive(Debug, Default)]
+pub struct Params {
+    layout: Vec<Param>,
+    values: Vec<[f32; 4]>,
+}
+
+impl Params {
+    pub fn new(layout: Vec<Param>) -> Self {
+        let values = layout.iter().map(|param| param.default).collect();
+        Params { layout, values }
+    }
+
+    /// Keeps the values of the parameters with the same name and type
+    pub fn set_layout(&mut self, layout: Vec<Param>) {
+        let values = layout
+            .iter()
+            .map(|param| {
+                self.layout
+                    .iter()
+                    .position(|old| old.same_type(param))
+                    .map(|index| param.clamp(self.values[index]))
+                    .unwrap_or(param.default)
+            })
+            .collect();
+        self.layout = layout;
+        self.values = values;
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.layout.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&Param, [f32; 4])> {
+        self.layout.iter().zip(self.values.iter().copied())
+    }
+
+    pub fn set(&mut self, index: usize, value: [f32; 4]) {
+        if let (Some(param), Some(current)) = (self.layout.get(index), self.values.get_mut(index)) {
+            *current = param.clamp(value);
+        }
+    }
+
+    pub fn reset(&mut self) {
+        self.values = self.layout.iter().map(|param| param.default).collect();
+    }
+
+    /// Content of the whole uniform buffer, the bytes of no parameter are zero
+    /// so nothing is left from a previous layout
+    pub fn bytes(&self) -> Vec<u8> {
+        let mut bytes = vec![0; BUFFER_SIZE as usize];
+        for (param, value) in self.iter() {
+            let start = param.offset as usize;
+            // larger structs are rejected by `reflect`
+            if start + param.components * 4 > bytes.len() {
+                continue;
+            }
+            for (index, component) in value[..param.components].iter().enumerate() {
+                let word = match param.scalar {
+                    Scalar::F32 => component.to_ne_bytes(),
+                    Scalar::I32 => (component.round() as i32).to_ne_bytes(),
+                    Scalar::U32 => (component.round() as u32).to_ne_bytes(),
+                };
+                bytes[start + index * 4..start + index * 4 + 4].copy_from_slice(&word);
+            }
+        }
+        bytes
+    }
+}
+
+/// Finds the struct of parameters in the module, the annotations are read
+/// from the comments in `source`. Matrices, arrays and nested structs are skipped,
+/// a struct larger than [`BUFFER_SIZE`] is an error.
+pub fn reflect(module: &Module, source: &str) -> Result<Vec<Param>, String> {
+    let variable = module.global_variables.iter().find(|(_, variable)| {
+        variable.space == AddressSpace::Uniform
+            && variable
+                .binding
+                .as_ref()
+                .is_some_and(|binding| binding.group == GROUP && binding.binding == BINDING)
+    });
+    let Some((_, variable)) = variable else {
+        return Ok(Vec::new());
+    };
+    let ty = &module.types[variable.ty];
+    let size = ty.inner.size(module.to_ctx());
+    if u64::from(size) > BUFFER_SIZE {
+        return Err(format!(
+            "The parameters take {size} bytes, the buffer has {BUFFER_SIZE}"
+        ));
+    }
+    let TypeInner::Struct { members, .. } = &ty.inner else {
+        return Ok(Vec::new());
+    };
+    let lines = ty
+        .name
+        .as_deref()
+        .map(|name| struct_lines(source, name))
+        .unwrap_or_default();
+
+    let params = members
+        .iter()
+        .enumerate()
+        .filter_map(|(index, member)| {
+            let (scalar, components) = match module.types[member.ty].inner {
+                TypeInner::Scalar(scalar) => (scalar, 1),
+                TypeInner::Vector { size, scalar } => (scalar, size as usize),
+                _ => return None,
+            };
+            let scalar = match (scalar.kind, scalar.width) {
+                (ScalarKind::Float, 4) => Scalar::F32,
+                (ScalarKind::Sint, 4) => Scalar::I32,
+                (ScalarKind::Uint, 4) => Scalar::U32,
+                _ => return None,
+            };
+            let name = member
+                .name
+                .clone()
+                .unwrap_or_else(|| format!("member{index}"));
+            let comment = lines
+                .iter()
+                .find(|(code, _)| has_word(code, &name))
+                .map(|(_, comment)| *comment)
+                .unwrap_or_default();
+            Some(param(name, scalar, components, member.offset, comment))
+        })
+        .collect();
+    Ok(params)
+}
+
+/// Control, range and default value of a member from its annotations
+fn param(name: String, scalar: Scalar, components: usize, offset: u32, comment: &str) -> Param {
+    let annotations = annotations(comment);
+    let find = |name: &str| {
+        annotations
+            .iter()
+            .find(|(annotation, _)| *annotation == name)
+            .map(|(_, arguments)| arguments.as_slice())
+    };
+
+    let is_float = scalar == Scalar::F32;
+    let control = if find("checkbox").is_some() && !is_float && components == 1 {
+        Control::Checkbox
+    } else if find("color").is_some() && is_float && components >= 3 {
+        Control::Color
+    } else if is_float && components == 2 {
+        Control::Pad
+    } else {
+        Control::Slider
+    };
+    let (min, max) = match (control, find("range")) {
+        (Control::Checkbox, _) => (0.0, 1.0),
+        (Control::Color, _) => (0.0, 1.0),
+        (_, Some(&[min, max])) if min < max => (min, max),
+        _ if is_float => (0.0, 1.0),
+        _ => (0.0, 10.0),
+    };
+
+    // a single default is used for every component, the alpha of a color is opaque
+    let mut default = [0.0; 4];
+    match find("default") {
+        Some(&[value]) => default = [value; 4],
+        Some(values) => {
+            for (component, value) in default.iter_mut().zip(values) {
+                *component = *value;
+            }
+            if control == Control::Color && values.len() < 4 {
+                default[3] = 1.0;
+            }
+        }
+        None if control == Control::Color => default = [1.0; 4],
+        None => (),
+    }
+    let mut param = Param {
+        name,
+        scalar,
+        components,
+        offset,
+        control,
+        min,
+        max,
+        default,
+    };
+    param.default = param.clamp(param.default);
+    param
+}
+
+/// `@name(arguments)` pairs of a comment, the arguments are numbers
+fn annotations(comment: &str) -> Vec<(&str, Vec<f32>)> {
+    comment
+        .split('@')
+        .skip(1)
+        .filter_map(|annotation| {
+            let name_end = annotation
+                .find(|c: char| !c.is_ascii_alphanumeric() && c != '_')
+                .unwrap_or(annotation.len());
+            let name = &annotation[..name_end];
+            let rest = annotation[name_end..].trim_start();
+            let arguments = rest
+                .strip_prefix('(')
+                .and_then(|rest| rest.split_once(')'))
+                .map(|(arguments, _)| {
+                    arguments
+                        .split(',')
+                        .filter_map(|argument| argument.trim().parse().ok())
+                        .collect()
+                })
+                .unwrap_or_default();
+            (!name.is_empty()).then_some((name, arguments))
+        })
+        .collect()
+}
+
+/// Code and comment of every line in the body of the struct or block named `name`
+fn struct_lines<'a>(source: &'a str, name: &str) -> Vec<(&'a str, &'a str)> {
+    let body = source.match_indices(name).find_map(|(start, _)| {
+        let end = start + name.len();
+        let is_word =
+            !source[..start].ends_with(is_identifier) && !source[end..].starts_with(is_identifier);
+        let rest = source[end..].trim_start();
+        (is_word && rest.starts_with('{')).then(|| &rest[1..])
+    });
+    let Some(body) = body else {
+        return Vec::new();
+    };
+    body.lines()
+        .map(|line| line.split_once("//").unwrap_or((line, "")))
+        .take_while(|(code, _)| !code.contains('}'))
+        .collect()
+}
+
+fn has_word(code: &str, word: &str) -> bool {
+    code.split(|c: char| !is_identifier(c))
+        .any(|part| part == word)
+}
+
+fn is_identifier(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_'
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bytes_cover_the_buffer() {
+        let mut params = Params::new(vec![
+            param(
+                "speed".to_owned(),
+                Scalar::F32,
+                1,
+                0,
+                "@range(0, 10) @default(2)",
+            ),
+            param("count".to_owned(), Scalar::I32, 1, 4, "@default(3)"),
+        ]);
+        let speed = param("speed".to_owned(), Scalar::F32, 1, 0, "@range(0, 10)");
+        params.set_layout(vec![speed]);
+        let bytes = params.bytes();
+        assert_eq!(bytes.len(), BUFFER_SIZE as usize);
+        // the member with the same name and type keeps its value
+        assert_eq!(bytes[..4], 2.0f32.to_ne_bytes());
+        // the member removed from the layout doesn't keep its value
+        assert!(bytes[4..].iter().all(|&byte| byte == 0));
+    }
+
+    #[test]
+    fn struct_larger_than_the_buffer() {
+        let source = "
+            struct Params {
+                speed: f32,
+                table: array<vec4<f32>, 256>,
+                last: f32,
+            }
+            @group(0) @binding(1) var<uniform> params: Params;
+        ";
+        let module = naga::front::wgsl::parse_str(source).expect("Should parse");
+        let error = reflect(&module, source).expect_err("Should not fit");
+        assert!(error.contains("the buffer has 4096"), "{error}");
+    }
+}
@@ -0,0 +1,131 @@
+use std::ops::Range;
+
+use crate::scene::channels::{CHANNELS_GLSL, CHANNELS_WGSL};
+use crate::scene::compute::{COMPUTE_GLSL, COMPUTE_WGSL};
+use crate::scene::globals::{GLOBALS_GLSL, GLOBALS_WGSL};
+
+/// Version of the GLSL shaders without a `#version` directive
+const GLSL_VERSION: &str = "#version 450";
+
+/// Full-screen triangle appended to the shaders without a `@vertex` entry point
+pub const FULLSCREEN_WGSL: &str = "
+// Built-in vertex stage of the fragment-only shaders.
+//
+// position - pixel coordinates, origin at the top left corner
+// uv       - 0..1 across the render target, origin at the top left corner
+struct FragmentInput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+}
+
+@vertex
+fn vs_main(@builtin(vertex_index) index: u32) -> FragmentInput {
+    let clip = vec2(f32(index % 2u) * 4.0 - 1.0, f32(index / 2u) * 4.0 - 1.0);
+    var out: FragmentInput;
+    out.position = vec4(clip, 0.0, 1.0);
+    out.uv = vec2(clip.x * 0.5 + 0.5, 0.5 - clip.y * 0.5);
+    return out;
+}
+";
+
+/// User shader with the built-in declarations prepended
+#[derive(Debug)]
+pub struct Composed {
+    pub source: String,
+    /// Length of the prepended declarations in bytes
+    pub offset: usize,
+    /// End of the user shader, followed by [`FULLSCREEN_WGSL`] in the fragment-only mode of WGSL
+    pub end: usize,
+    /// The shader has no `@vertex` entry point and is drawn with a full-screen triangle
+    pub fragment_only: bool,
+}
+
+impl Composed {
+    /// Maps a span of the composed source back to the user shader,
+    /// `None` if the span points into the built-in declarations
+    pub fn user_span(&self, span: naga::Span) -> Option<Range<usize>> {
+        let range = span.to_range()?;
+        if range.start < self.offset || range.end > self.end {
+            return None;
+        }
+        Some(range.start - self.offset..range.end - self.offset)
+    }
+}
+
+/// Prepends built-in declarations to the user shader,
+/// so both the validator and the pipeline see the same source.
+/// The full-screen triangle is appended in the `fragment_only` mode.
+pub fn compose(shader: &str, fragment_only: bool) -> Composed {
+    let mut source = String::new();
+    for declarations in [GLOBALS_WGSL, CHANNELS_WGSL, COMPUTE_WGSL] {
+        source.push_str(declarations);
+        source.push('\n');
+    }
+    let offset = source.len();
+    source.push_str(shader);
+    let end = source.len();
+    if fragment_only {
+        source.push_str(FULLSCREEN_WGSL);
+    }
+    Composed {
+        source,
+        offset,
+        end,
+        fragment_only,
+    }
+}
+
+/// Inserts the built-in declarations after the `#version` directive of a GLSL shader.
+/// The directive is moved to the first line and blanked in place,
+/// so the offsets of the user shader stay the same.
+pub fn compose_glsl(shader: &str, stage: naga::ShaderStage) -> Composed {
+    let mut user = shader.to_owned();
+    let mut source = String::new();
+    match find_version(shader) {
+        Some(range) => {
+            source.push_str(&shader[range.clone()]);
+            user.replace_range(range.clone(), &" ".repeat(range.len()));
+        }
+        None => source.push_str(GLSL_VERSION),
+    }
+    source.push('\n');
+    for declarations in [GLOBALS_GLSL, CHANNELS_GLSL, COMPUTE_GLSL] {
+        source.push_str(declarations);
+        source.push('\n');
+    }
+    let offset = source.len();
+    source.push_str(&user);
+    Composed {
+        end: source.len(),
+        source,
+        offset,
+        // a fragment shader is paired with the built-in vertex stage
+        fragment_only: stage == naga::ShaderStage::Fragment,
+    }
+}
+
+/// SPIR-V modules are used as they are, with the bindings they declare
+pub fn compose_spirv(module: &naga::Module) -> Composed {
+    Composed {
+        source: String::new(),
+        offset: 0,
+        end: 0,
+        fragment_only: !module
+            .entry_points
+            .iter()
+            .any(|entry_point| entry_point.stage == naga::ShaderStage::Vertex),
+    }
+}
+
+/// Byte range of the `#version` line, without the line break
+fn find_version(shader: &str) -> Option<Range<usize>> {
+    let mut start = 0;
+    for line in shader.split_inclusive('\n') {
+        let code = line.trim_end_matches(['\r', '\n']);
+        if code.trim_start().starts_with("#version") {
+            return Some(start..start + code.len());
+        }
+        start += line.len();
+    }
+    None
+}
@@ -0,0 +1,184 @@
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+use std::path::PathBuf;
+use std::process::{Child, Command, Stdio};
+
+use image::RgbaImage;
+
+use crate::scene::globals::Globals;
+
+/// External encoder fed with the raw frames, writes a video and a GIF next to the APNG
+const ENCODER: &str = "ffmpeg";
+
+/// Settings of an animation rendered offscreen
+#[derive(Debug, Clone)]
+pub struct Recording {
+    /// Animated PNG, the numbered frames go to the `{name}_frames` directory next to it
+    pub path: PathBuf,
+    pub width: u32,
+    pub height: u32,
+    pub frames: u32,
+    pub fps: u32,
+    /// Pipe the frames to [`ENCODER`] if it's installed
+    pub video: bool,
+}
+
+impl Recording {
+    /// Globals of the frame, advanced by a fixed timestep from zero,
+    /// so the result doesn't depend on the speed of the machine
+    pub fn globals(&self, start: Globals, frame: u32) -> Globals {
+        let fps = self.fps.max(1) as f32;
+        let time = frame as f32 / fps;
+        let mut globals = start;
+        globals.time = time;
+        globals.time_delta = 1.0 / fps;
+        globals.frame = frame;
+        globals.date[3] = start.date[3] + time;
+        globals
+    }
+}
+
+/// Writes the rendered frames of a [`Recording`]
+pub struct Recorder {
+    frames_directory: PathBuf,
+    animation: png::Writer<BufWriter<File>>,
+    encoder: Option<Child>,
+    /// Problems of the encoder, the PNG files are written without it
+    warnings: Vec<String>,
+    frame: u32,
+}
+
+impl Recorder {
+    pub fn new(recording: &Recording) -> Result<Self, String> {
+        let mut warnings = Vec::new();
+        let encoder = match recording.video.then(|| spawn_encoder(recording)) {
+            Some(Ok(encoder)) => Some(encoder),
+            Some(Err(e)) => {
+                warnings.push(e);
+                None
+            }
+            None => None,
+        };
+
+        let path = &recording.path;
+        let frames_directory = path.with_file_name(format!(
+            "{}_frames",
+            path.file_stem().unwrap_or_default().to_string_lossy()
+        ));
+        fs::create_dir_all(&frames_directory)
+            .map_err(|e| format!("Error creating {}: {e}", frames_directory.display()))?;
+
+        let file =
+            File::create(path).map_err(|e| format!("Error creating {}: {e}", path.display()))?;
+        let mut apng = png::Encoder::new(BufWriter::new(file), recording.width, recording.height);
+        apng.set_color(png::ColorType::Rgba);
+        apng.set_depth(png::BitDepth::Eight);
+        let fps = recording.fps.clamp(1, u16::MAX.into()) as u16;
+        let animation = apng
+            .set_animated(recording.frames, 0)
+            .and_then(|()| apng.set_frame_delay(1, fps))
+            .and_then(|()| apng.write_header())
+            .map_err(|e| format!("Error writing {}: {e}", path.display()))?;
+
+        Ok(Recorder {
+            frames_directory,
+            animation,
+            encoder,
+            warnings,
+            frame: 0,
+        })
+    }
+
+    pub fn add_frame(&mut self, image: &RgbaImage) -> Result<(), String> {
+        let path = self.frames_directory.join(format!("{:05}.png", self.frame));
+        image
+            .save(&path)
+            .map_err(|e| format!("Error writing {}: {e}", path.display()))?;
+        self.animation
+            .write_image_data(image.as_raw())
+            .map_err(|e| format!("Error writing the animation: {e}"))?;
+        if let Some(stdin) = self
+            .encoder
+            .as_mut()
+            .and_then(|encoder| encoder.stdin.as_mut())
+            && let Err(e) = stdin.write_all(image.as_raw())
+        {
+            self.warnings
+                .push(format!("Error piping the frame to {ENCODER}: {e}"));
+            // the encoder stopped reading, the rest is recorded without it
+            if let Some(mut encoder) = self.encoder.take() {
+                drop(encoder.stdin.take());
+                let _ = encoder.wait();
+            }
+        }
+        self.frame += 1;
+        Ok(())
+    }
+
+    /// Completes the animation and waits for the encoder to exit,
+    /// returns the problems of the encoder
+    pub fn finish(mut self) -> Result<Vec<String>, String> {
+        self.animation
+            .finish()
+            .map_err(|e| format!("Error writing the animation: {e}"))?;
+        if let Some(mut encoder) = self.encoder {
+            // closing the input ends the stream
+            drop(encoder.stdin.take());
+            match encoder.wait() {
+                Ok(status) if !status.success() => {
+                    self.warnings
+                        .push(format!("{ENCODER} exited with {status}"));
+                }
+                Ok(_) => (),
+                Err(e) => self
+                    .warnings
+                    .push(format!("Error waiting for {ENCODER}: {e}")),
+            }
+        }
+        Ok(self.warnings)
+    }
+}
+
+/// Starts [`ENCODER`] reading raw RGBA frames from stdin,
+/// it writes `{name}.mp4` and `{name}.gif` next to the animated PNG
+fn spawn_encoder(recording: &Recording) -> Result<Child, String> {
+    let Recording {
+        path,
+        width,
+        height,
+        fps,
+        ..
+    } = recording;
+    let output = |extension| path.with_extension(extension).into_os_string();
+    Command::new(ENCODER)
+        .args([
+            "-y",
+            "-loglevel",
+            "error",
+            "-f",
+            "rawvideo",
+            "-pix_fmt",
+            "rgba",
+        ])
+        .args(["-s", &format!("{width}x{height}")])
+        .args(["-framerate", &fps.to_string()])
+        .args(["-i", "-"])
+        // most players expect even sizes in yuv420p
+        .args([
+            "-vf",
+            "pad=ceil(iw/2)*2:ceil(ih/2)*2",
+            "-pix_fmt",
+            "yuv420p",
+        ])
+        .arg(output("mp4"))
+        .arg(output("gif"))
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .spawn()
+        .map_err(|e| match e.kind() {
+            io::ErrorKind::NotFound => {
+                format!("{ENCODER} is not found, recorded without the video")
+            }
+            _ => format!("Error starting {ENCODER}: {e}"),
+        })
+}
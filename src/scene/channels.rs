@@ -0,0 +1,352 @@
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use iced_wgpu::wgpu::{self, BindGroup, BindGroupLayout, Device, Queue, Sampler, TextureView};
+use wgpu::util::DeviceExt;
+
+use super::graph::Pass;
+
+/// Number of the texture input channels
+pub const CHANNEL_COUNT: usize = 4;
+
+/// WGSL declarations of the input channels, prepended to every WGSL shader
+/// by [`crate::prelude::compose`].
+pub const CHANNELS_WGSL: &str = "\
+// Texture input channels, black when nothing is bound.
+//
+// iChannelN - image or buffer of the channel N, origin at the top left corner
+// iSamplerN - sampler with the filter and wrap mode of the channel N
+@group(1) @binding(0)
+var iChannel0: texture_2d<f32>;
+@group(1) @binding(1)
+var iSampler0: sampler;
+@group(1) @binding(2)
+var iChannel1: texture_2d<f32>;
+@group(1) @binding(3)
+var iSampler1: sampler;
+@group(1) @binding(4)
+var iChannel2: texture_2d<f32>;
+@group(1) @binding(5)
+var iSampler2: sampler;
+@group(1) @binding(6)
+var iChannel3: texture_2d<f32>;
+@group(1) @binding(7)
+var iSampler3: sampler;
+";
+
+/// GLSL declarations of the input channels, prepended to the GLSL shaders
+/// by [`crate::prelude::compose_glsl`].
+pub const CHANNELS_GLSL: &str = "\
+// Texture input channels, sample them with
+// texture(sampler2D(iChannel0, iSampler0), uv)
+layout(set = 1, binding = 0) uniform texture2D iChannel0;
+layout(set = 1, binding = 1) uniform sampler iSampler0;
+layout(set = 1, binding = 2) uniform texture2D iChannel1;
+layout(set = 1, binding = 3) uniform sampler iSampler1;
+layout(set = 1, binding = 4) uniform texture2D iChannel2;
+layout(set = 1, binding = 5) uniform sampler iSampler2;
+layout(set = 1, binding = 6) uniform texture2D iChannel3;
+layout(set = 1, binding = 7) uniform sampler iSampler3;
+";
+
+/// File extensions of the images which can be bound to a channel
+pub const IMAGE_EXTENSIONS: [&str; 4] = ["png", "jpg", "jpeg", "hdr"];
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Filter {
+    #[default]
+    Linear,
+    Nearest,
+}
+
+impl Filter {
+    pub const ALL: [Filter; 2] = [Filter::Linear, Filter::Nearest];
+}
+
+impl fmt::Display for Filter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Filter::Linear => write!(f, "linear"),
+            Filter::Nearest => write!(f, "nearest"),
+        }
+    }
+}
+
+impl From<Filter> for wgpu::FilterMode {
+    fn from(filter: Filter) -> Self {
+        match filter {
+            Filter::Linear => wgpu::FilterMode::Linear,
+            Filter::Nearest => wgpu::FilterMode::Nearest,
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Wrap {
+    #[default]
+    Repeat,
+    Clamp,
+    Mirror,
+}
+
+impl Wrap {
+    pub const ALL: [Wrap; 3] = [Wrap::Repeat, Wrap::Clamp, Wrap::Mirror];
+}
+
+impl fmt::Display for Wrap {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Wrap::Repeat => write!(f, "repeat"),
+            Wrap::Clamp => write!(f, "clamp"),
+            Wrap::Mirror => write!(f, "mirror"),
+        }
+    }
+}
+
+impl From<Wrap> for wgpu::AddressMode {
+    fn from(wrap: Wrap) -> Self {
+        match wrap {
+            Wrap::Repeat => wgpu::AddressMode::Repeat,
+            Wrap::Clamp => wgpu::AddressMode::ClampToEdge,
+            Wrap::Mirror => wgpu::AddressMode::MirrorRepeat,
+        }
+    }
+}
+
+/// Texture sampled by an input channel
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub enum Source {
+    /// Black texture
+    #[default]
+    None,
+    Image(PathBuf),
+    /// Output of a buffer pass, the previous frame when the pass reads itself
+    /// or a buffer rendered after it
+    Buffer(Pass),
+    /// Storage texture written by the compute pass
+    Storage(usize),
+}
+
+impl fmt::Display for Source {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Source::None => write!(f, "none"),
+            Source::Image(path) => match path.file_name() {
+                Some(name) => write!(f, "{}", name.to_string_lossy()),
+                None => write!(f, "{}", path.display()),
+            },
+            Source::Buffer(pass) => write!(f, "{pass}"),
+            Source::Storage(index) => write!(f, "storage{index}"),
+        }
+    }
+}
+
+/// What is bound to an input channel
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Channel {
+    pub source: Source,
+    pub filter: Filter,
+    pub wrap: Wrap,
+}
+
+/// Decoded image ready to be uploaded into a texture
+pub struct ChannelImage {
+    width: u32,
+    height: u32,
+    format: wgpu::TextureFormat,
+    data: Vec<u8>,
+}
+
+impl ChannelImage {
+    /// Reads a PNG, JPEG or HDR file,
+    /// HDR images keep their range in a half float texture
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let image =
+            image::open(path).map_err(|e| format!("Error reading {}: {e}", path.display()))?;
+        let (width, height) = (image.width(), image.height());
+        let is_float = matches!(
+            image,
+            image::DynamicImage::ImageRgb32F(_) | image::DynamicImage::ImageRgba32F(_)
+        );
+        if is_float {
+            let data = image
+                .to_rgba32f()
+                .into_raw()
+                .into_iter()
+                .flat_map(|value| half::f16::from_f32(value).to_le_bytes())
+                .collect();
+            Ok(ChannelImage {
+                width,
+                height,
+                format: wgpu::TextureFormat::Rgba16Float,
+                data,
+            })
+        } else {
+            Ok(ChannelImage {
+                width,
+                height,
+                format: wgpu::TextureFormat::Rgba8UnormSrgb,
+                data: image.to_rgba8().into_raw(),
+            })
+        }
+    }
+
+    /// A single black pixel
+    fn placeholder() -> Self {
+        ChannelImage {
+            width: 1,
+            height: 1,
+            format: wgpu::TextureFormat::Rgba8UnormSrgb,
+            data: vec![0, 0, 0, 255],
+        }
+    }
+
+    fn create_view(&self, device: &Device, queue: &Queue) -> TextureView {
+        let texture = device.create_texture_with_data(
+            queue,
+            &wgpu::TextureDescriptor {
+                label: Some("Texture - Channel"),
+                size: wgpu::Extent3d {
+                    width: self.width,
+                    height: self.height,
+                    depth_or_array_layers: 1,
+                },
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: wgpu::TextureDimension::D2,
+                format: self.format,
+                usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+                view_formats: &[],
+            },
+            wgpu::util::TextureDataOrder::LayerMajor,
+            &self.data,
+        );
+        texture.create_view(&wgpu::TextureViewDescriptor::default())
+    }
+}
+
+fn create_sampler(device: &Device, filter: Filter, wrap: Wrap) -> Sampler {
+    device.create_sampler(&wgpu::SamplerDescriptor {
+        label: Some("Sampler - Channel"),
+        address_mode_u: wrap.into(),
+        address_mode_v: wrap.into(),
+        address_mode_w: wrap.into(),
+        mag_filter: filter.into(),
+        min_filter: filter.into(),
+        ..Default::default()
+    })
+}
+
+/// Layout of the input channels, bound to group 1
+pub fn create_bind_group_layout(device: &Device) -> BindGroupLayout {
+    let entries: Vec<_> = (0..CHANNEL_COUNT as u32)
+        .flat_map(|index| {
+            [
+                wgpu::BindGroupLayoutEntry {
+                    binding: index * 2,
+                    visibility: wgpu::ShaderStages::FRAGMENT | wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: index * 2 + 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT | wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+            ]
+        })
+        .collect();
+    device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+        label: Some("Bind Group Layout - Channels"),
+        entries: &entries,
+    })
+}
+
+/// A single black pixel, sampled by the channels with nothing bound
+pub fn create_placeholder(device: &Device, queue: &Queue) -> TextureView {
+    ChannelImage::placeholder().create_view(device, queue)
+}
+
+/// Loaded images and samplers of the input channels of a pass
+pub struct Inputs {
+    sources: Vec<Source>,
+    images: Vec<Option<TextureView>>,
+    samplers: Vec<Sampler>,
+}
+
+impl Inputs {
+    pub fn new(device: &Device) -> Self {
+        Inputs {
+            sources: vec![Source::None; CHANNEL_COUNT],
+            images: (0..CHANNEL_COUNT).map(|_| None).collect(),
+            samplers: (0..CHANNEL_COUNT)
+                .map(|_| create_sampler(device, Filter::default(), Wrap::default()))
+                .collect(),
+        }
+    }
+
+    /// Binds the source and the sampler settings of `channel` to the channel `index`,
+    /// keeps the previous source if the image can't be read
+    pub fn set(
+        &mut self,
+        device: &Device,
+        queue: &Queue,
+        index: usize,
+        channel: &Channel,
+    ) -> Result<(), String> {
+        self.images[index] = match &channel.source {
+            Source::Image(path) => Some(ChannelImage::load(path)?.create_view(device, queue)),
+            Source::None | Source::Buffer(_) | Source::Storage(_) => None,
+        };
+        self.sources[index] = channel.source.clone();
+        self.samplers[index] = create_sampler(device, channel.filter, channel.wrap);
+        Ok(())
+    }
+
+    /// Buffers and storage textures are resolved with `resolve` on every frame,
+    /// buffers are swapped after each pass
+    pub fn create_bind_group<'a>(
+        &'a self,
+        device: &Device,
+        layout: &BindGroupLayout,
+        placeholder: &'a TextureView,
+        resolve: impl Fn(&Source) -> Option<&'a TextureView>,
+    ) -> BindGroup {
+        let entries: Vec<_> = (0..CHANNEL_COUNT)
+            .flat_map(|index| {
+                let view = match (&self.sources[index], &self.images[index]) {
+                    (Source::Image(_), Some(image)) => image,
+                    (source, _) => resolve(source).unwrap_or(placeholder),
+                };
+                [
+                    wgpu::BindGroupEntry {
+                        binding: index as u32 * 2,
+                        resource: wgpu::BindingResource::TextureView(view),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: index as u32 * 2 + 1,
+                        resource: wgpu::BindingResource::Sampler(&self.samplers[index]),
+                    },
+                ]
+            })
+            .collect();
+        device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Bind Group - Channels"),
+            layout,
+            entries: &entries,
+        })
+    }
+}
+
+pub fn has_image_extension(path: &Path) -> bool {
+    path.extension()
+        .and_then(|extension| extension.to_str())
+        .is_some_and(|extension| {
+            IMAGE_EXTENSIONS.contains(&extension.to_ascii_lowercase().as_str())
+        })
+}
@@ -0,0 +1,291 @@
+use std::fmt;
+
+use iced_wgpu::wgpu::{
+    self, BindGroup, BindGroupLayout, Buffer, ComputePipeline, Device, PipelineLayoutDescriptor,
+    TextureView,
+};
+
+use crate::language::Shader;
+use crate::validator::Validation;
+
+/// Number of the storage textures written by the compute pass
+pub const STORAGE_COUNT: usize = 4;
+
+/// Format of the storage textures, filterable so the render passes can sample them
+pub const STORAGE_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba16Float;
+
+/// Number of `vec4<f32>` elements in the persistent storage buffer
+pub const DATA_LENGTH: u64 = 262144;
+
+/// WGSL declarations of the compute resources, prepended to every WGSL shader
+/// by [`crate::prelude::compose`].
+pub const COMPUTE_WGSL: &str = "\
+// Resources of the compute pass, not available in the render passes.
+//
+// storageN - window sized texture, sampled by the render passes
+//            when bound to an input channel
+// data     - persistent buffer, kept between frames,
+//            initialize it when globals.frame is 0
+@group(2) @binding(0)
+var storage0: texture_storage_2d<rgba16float, write>;
+@group(2) @binding(1)
+var storage1: texture_storage_2d<rgba16float, write>;
+@group(2) @binding(2)
+var storage2: texture_storage_2d<rgba16float, write>;
+@group(2) @binding(3)
+var storage3: texture_storage_2d<rgba16float, write>;
+@group(2) @binding(4)
+var<storage, read_write> data: array<vec4<f32>>;
+";
+
+/// GLSL declarations of the compute resources, prepended to the GLSL shaders
+/// by [`crate::prelude::compose_glsl`].
+pub const COMPUTE_GLSL: &str = "\
+// Resources of the compute pass, the same as in WGSL
+layout(set = 2, binding = 0, rgba16f) uniform writeonly image2D storage0;
+layout(set = 2, binding = 1, rgba16f) uniform writeonly image2D storage1;
+layout(set = 2, binding = 2, rgba16f) uniform writeonly image2D storage2;
+layout(set = 2, binding = 3, rgba16f) uniform writeonly image2D storage3;
+layout(set = 2, binding = 4, std430) buffer Data {
+    vec4 data[];
+};
+";
+
+/// Number of workgroups dispatched for every compute entry point
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Dispatch {
+    /// Enough workgroups to cover every pixel of the window
+    #[default]
+    Resolution,
+    Fixed([u32; 3]),
+}
+
+impl Dispatch {
+    fn workgroups(self, workgroup_size: [u32; 3], width: u32, height: u32) -> [u32; 3] {
+        match self {
+            Dispatch::Resolution => [
+                width.div_ceil(workgroup_size[0].max(1)),
+                height.div_ceil(workgroup_size[1].max(1)),
+                1,
+            ],
+            Dispatch::Fixed(workgroups) => workgroups,
+        }
+    }
+}
+
+impl fmt::Display for Dispatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Dispatch::Resolution => write!(f, "resolution"),
+            Dispatch::Fixed(_) => write!(f, "fixed"),
+        }
+    }
+}
+
+/// Storage textures and the persistent buffer, bound to group 2 of the compute pass
+pub struct Storage {
+    pub bind_group_layout: BindGroupLayout,
+    pub bind_group: BindGroup,
+    views: Vec<TextureView>,
+    data: Buffer,
+}
+
+impl Storage {
+    pub fn new(device: &Device, width: u32, height: u32) -> Self {
+        let mut entries: Vec<_> = (0..STORAGE_COUNT as u32)
+            .map(|binding| wgpu::BindGroupLayoutEntry {
+                binding,
+                visibility: wgpu::ShaderStages::COMPUTE,
+                ty: wgpu::BindingType::StorageTexture {
+                    access: wgpu::StorageTextureAccess::WriteOnly,
+                    format: STORAGE_FORMAT,
+                    view_dimension: wgpu::TextureViewDimension::D2,
+                },
+                count: None,
+            })
+            .collect();
+        entries.push(wgpu::BindGroupLayoutEntry {
+            binding: STORAGE_COUNT as u32,
+            visibility: wgpu::ShaderStages::COMPUTE,
+            ty: wgpu::BindingType::Buffer {
+                ty: wgpu::BufferBindingType::Storage { read_only: false },
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            },
+            count: None,
+        });
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Bind Group Layout - Storage"),
+            entries: &entries,
+        });
+
+        Self::with_layout(device, bind_group_layout, width, height)
+    }
+
+    /// Storage with the same layout and its own textures and buffer
+    pub fn new_like(&self, device: &Device, width: u32, height: u32) -> Self {
+        Self::with_layout(device, self.bind_group_layout.clone(), width, height)
+    }
+
+    fn with_layout(
+        device: &Device,
+        bind_group_layout: BindGroupLayout,
+        width: u32,
+        height: u32,
+    ) -> Self {
+        let data = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Storage Buffer - Data"),
+            size: DATA_LENGTH * 16,
+            usage: wgpu::BufferUsages::STORAGE,
+            mapped_at_creation: false,
+        });
+
+        let views = Self::create_views(device, width, height);
+        let bind_group = Self::create_bind_group(device, &bind_group_layout, &views, &data);
+
+        Storage {
+            bind_group_layout,
+            bind_group,
+            views,
+            data,
+        }
+    }
+
+    /// Textures follow the size of the window, the buffer is kept
+    pub fn resize(&mut self, device: &Device, width: u32, height: u32) {
+        self.views = Self::create_views(device, width, height);
+        self.bind_group =
+            Self::create_bind_group(device, &self.bind_group_layout, &self.views, &self.data);
+    }
+
+    pub fn view(&self, index: usize) -> &TextureView {
+        &self.views[index]
+    }
+
+    fn create_views(device: &Device, width: u32, height: u32) -> Vec<TextureView> {
+        (0..STORAGE_COUNT)
+            .map(|_| {
+                device
+                    .create_texture(&wgpu::TextureDescriptor {
+                        label: Some("Texture - Storage"),
+                        size: wgpu::Extent3d {
+                            width: width.max(1),
+                            height: height.max(1),
+                            depth_or_array_layers: 1,
+                        },
+                        mip_level_count: 1,
+                        sample_count: 1,
+                        dimension: wgpu::TextureDimension::D2,
+                        format: STORAGE_FORMAT,
+                        usage: wgpu::TextureUsages::STORAGE_BINDING
+                            | wgpu::TextureUsages::TEXTURE_BINDING,
+                        view_formats: &[],
+                    })
+                    .create_view(&wgpu::TextureViewDescriptor::default())
+            })
+            .collect()
+    }
+
+    fn create_bind_group(
+        device: &Device,
+        layout: &BindGroupLayout,
+        views: &[TextureView],
+        data: &Buffer,
+    ) -> BindGroup {
+        let mut entries: Vec<_> = views
+            .iter()
+            .enumerate()
+            .map(|(binding, view)| wgpu::BindGroupEntry {
+                binding: binding as u32,
+                resource: wgpu::BindingResource::TextureView(view),
+            })
+            .collect();
+        entries.push(wgpu::BindGroupEntry {
+            binding: STORAGE_COUNT as u32,
+            resource: data.as_entire_binding(),
+        });
+        device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Bind Group - Storage"),
+            layout,
+            entries: &entries,
+        })
+    }
+}
+
+struct EntryPoint {
+    pipeline: ComputePipeline,
+    workgroup_size: [u32; 3],
+}
+
+/// Pipelines of every `@compute` entry point of a shader,
+/// dispatched in the declaration order
+pub struct ComputeData {
+    entry_points: Vec<EntryPoint>,
+}
+
+impl ComputeData {
+    pub fn new_with_validation(
+        device: &Device,
+        bind_group_layouts: &[&BindGroupLayout],
+        shader: &Shader,
+        validation: &Validation,
+    ) -> Result<Self, String> {
+        let entry_points: Vec<_> = validation
+            .module
+            .entry_points
+            .iter()
+            .filter(|entry_point| entry_point.stage == naga::ShaderStage::Compute)
+            .map(|entry_point| (entry_point.name.clone(), entry_point.workgroup_size))
+            .collect();
+        if entry_points.is_empty() {
+            return Err("The compute pass has no compute entry points".to_owned());
+        }
+
+        device.push_error_scope(wgpu::ErrorFilter::Validation);
+
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: None,
+            source: super::shader_source(shader, validation.module.clone(), &validation.composed),
+        });
+
+        let pipeline_layout = device.create_pipeline_layout(&PipelineLayoutDescriptor {
+            label: None,
+            bind_group_layouts,
+            push_constant_ranges: &[],
+        });
+
+        let entry_points = entry_points
+            .into_iter()
+            .map(|(name, workgroup_size)| EntryPoint {
+                pipeline: device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+                    label: Some(&name),
+                    layout: Some(&pipeline_layout),
+                    module: &shader,
+                    entry_point: Some(&name),
+                    compilation_options: Default::default(),
+                    cache: None,
+                }),
+                workgroup_size,
+            })
+            .collect();
+
+        if let Some(error) = futures::executor::block_on(device.pop_error_scope()) {
+            return Err(error.to_string());
+        }
+        Ok(ComputeData { entry_points })
+    }
+
+    pub fn dispatch(
+        &self,
+        pass: &mut wgpu::ComputePass<'_>,
+        dispatch: Dispatch,
+        width: u32,
+        height: u32,
+    ) {
+        for entry_point in &self.entry_points {
+            let [x, y, z] = dispatch.workgroups(entry_point.workgroup_size, width, height);
+            pass.set_pipeline(&entry_point.pipeline);
+            pass.dispatch_workgroups(x, y, z);
+        }
+    }
+}
@@ -0,0 +1,170 @@
+use bytemuck::{Pod, Zeroable};
+use iced_wgpu::wgpu::{self, BindGroup, BindGroupLayout, Buffer, Device, Queue};
+use wgpu::util::DeviceExt;
+
+use crate::params;
+
+/// WGSL declaration of [`Globals`], prepended to every WGSL shader
+/// by [`crate::prelude::compose`].
+pub const GLOBALS_WGSL: &str = "\
+// Built-in uniforms, updated every frame.
+//
+// resolution - size of the render target in pixels
+// time       - playback time in seconds
+// time_delta - time since the previous frame in seconds
+// mouse      - xy: cursor position in pixels while a button is held,
+//              zw: position of the last click, negative when released
+// date       - x: year, y: month (0-11), z: day (1-31), w: seconds since midnight (UTC)
+// frame      - index of the current frame
+//
+// Pixel coordinates have the origin at the top left corner,
+// same as @builtin(position).
+struct Globals {
+    resolution: vec2<f32>,
+    time: f32,
+    time_delta: f32,
+    mouse: vec4<f32>,
+    date: vec4<f32>,
+    frame: u32,
+}
+
+@group(0) @binding(0)
+var<uniform> globals: Globals;
+";
+
+/// GLSL declaration of [`Globals`], prepended to the GLSL shaders
+/// by [`crate::prelude::compose_glsl`].
+pub const GLOBALS_GLSL: &str = "\
+// Built-in uniforms, the same as in WGSL, e.g. globals.time
+layout(set = 0, binding = 0) uniform Globals {
+    vec2 resolution;
+    float time;
+    float time_delta;
+    vec4 mouse;
+    vec4 date;
+    uint frame;
+} globals;
+";
+
+/// Built-in uniforms, mirrors the `Globals` struct in [`GLOBALS_WGSL`].
+#[repr(C)]
+#[derive(Debug, Default, Clone, Copy, Pod, Zeroable)]
+pub struct Globals {
+    pub resolution: [f32; 2],
+    pub time: f32,
+    pub time_delta: f32,
+    pub mouse: [f32; 4],
+    pub date: [f32; 4],
+    pub frame: u32,
+    _padding: [u32; 3],
+}
+
+impl Globals {
+    /// Sets `date` from the system clock.
+    pub fn set_date_now(&mut self) {
+        let seconds = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|duration| duration.as_secs_f64())
+            .unwrap_or_default();
+        let days = (seconds / 86400.0).floor();
+        let (year, month, day) = civil_from_days(days as i64);
+        self.date = [
+            year as f32,
+            (month - 1) as f32,
+            day as f32,
+            (seconds - days * 86400.0) as f32,
+        ];
+    }
+}
+
+// Converts days since 1970-01-01 into (year, month, day),
+// see http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z.rem_euclid(146097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+pub struct GlobalsBuffer {
+    pub buffer: Buffer,
+    /// Parameters of the shader at binding 1, see [`crate::params`]
+    pub params_buffer: Buffer,
+    pub bind_group_layout: BindGroupLayout,
+    pub bind_group: BindGroup,
+}
+
+impl GlobalsBuffer {
+    pub fn new(device: &Device) -> Self {
+        let buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Uniform Buffer - Globals"),
+            contents: bytemuck::bytes_of(&Globals::default()),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+
+        let params_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Uniform Buffer - Params"),
+            size: params::BUFFER_SIZE,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let uniform = |binding, size| wgpu::BindGroupLayoutEntry {
+            binding,
+            visibility: wgpu::ShaderStages::VERTEX_FRAGMENT | wgpu::ShaderStages::COMPUTE,
+            ty: wgpu::BindingType::Buffer {
+                ty: wgpu::BufferBindingType::Uniform,
+                has_dynamic_offset: false,
+                min_binding_size: wgpu::BufferSize::new(size),
+            },
+            count: None,
+        };
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Bind Group Layout - Globals"),
+            entries: &[
+                uniform(0, size_of::<Globals>() as u64),
+                uniform(params::BINDING, params::BUFFER_SIZE),
+            ],
+        });
+
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Bind Group - Globals"),
+            layout: &bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: params::BINDING,
+                    resource: params_buffer.as_entire_binding(),
+                },
+            ],
+        });
+
+        GlobalsBuffer {
+            buffer,
+            params_buffer,
+            bind_group_layout,
+            bind_group,
+        }
+    }
+
+    pub fn write(&self, queue: &Queue, globals: &Globals) {
+        queue.write_buffer(&self.buffer, 0, bytemuck::bytes_of(globals));
+    }
+
+    /// The bytes past the end of the struct in the shader are ignored
+    pub fn write_params(&self, queue: &Queue, bytes: &[u8]) {
+        let length = bytes.len().min(params::BUFFER_SIZE as usize);
+        if length > 0 {
+            queue.write_buffer(&self.params_buffer, 0, &bytes[..length]);
+        }
+    }
+}
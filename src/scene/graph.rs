@@ -0,0 +1,118 @@
+use std::fmt;
+
+use iced_wgpu::wgpu::{self, Device, TextureView};
+
+/// Number of the passes in the render graph
+pub const PASS_COUNT: usize = 6;
+
+/// Format of the buffer pass outputs, keeps values outside of 0..1 for feedback
+pub const BUFFER_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba16Float;
+
+/// Named pass of the render graph
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Pass {
+    BufferA,
+    BufferB,
+    BufferC,
+    BufferD,
+    /// Rendered into the window
+    #[default]
+    Image,
+    /// Runs every `@compute` entry point of the shader
+    Compute,
+}
+
+impl Pass {
+    /// Passes in the render order
+    pub const ALL: [Pass; PASS_COUNT] = [
+        Pass::Compute,
+        Pass::BufferA,
+        Pass::BufferB,
+        Pass::BufferC,
+        Pass::BufferD,
+        Pass::Image,
+    ];
+    pub const BUFFERS: [Pass; 4] = [Pass::BufferA, Pass::BufferB, Pass::BufferC, Pass::BufferD];
+
+    pub fn index(self) -> usize {
+        self as usize
+    }
+
+    pub fn is_buffer(self) -> bool {
+        Pass::BUFFERS.contains(&self)
+    }
+
+    /// Single letter shown in the tab titles
+    pub fn short_name(self) -> &'static str {
+        match self {
+            Pass::BufferA => "A",
+            Pass::BufferB => "B",
+            Pass::BufferC => "C",
+            Pass::BufferD => "D",
+            Pass::Image => "",
+            Pass::Compute => "CS",
+        }
+    }
+}
+
+impl fmt::Display for Pass {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Pass::BufferA => write!(f, "Buffer A"),
+            Pass::BufferB => write!(f, "Buffer B"),
+            Pass::BufferC => write!(f, "Buffer C"),
+            Pass::BufferD => write!(f, "Buffer D"),
+            Pass::Image => write!(f, "Image"),
+            Pass::Compute => write!(f, "Compute"),
+        }
+    }
+}
+
+/// Offscreen output of a buffer pass,
+/// the pass renders into the back texture while reading the front one
+pub struct Target {
+    views: [TextureView; 2],
+    /// Index of the texture with the latest frame
+    front: usize,
+}
+
+impl Target {
+    pub fn new(device: &Device, width: u32, height: u32) -> Self {
+        let create_view = || {
+            device
+                .create_texture(&wgpu::TextureDescriptor {
+                    label: Some("Texture - Buffer"),
+                    size: wgpu::Extent3d {
+                        width: width.max(1),
+                        height: height.max(1),
+                        depth_or_array_layers: 1,
+                    },
+                    mip_level_count: 1,
+                    sample_count: 1,
+                    dimension: wgpu::TextureDimension::D2,
+                    format: BUFFER_FORMAT,
+                    usage: wgpu::TextureUsages::RENDER_ATTACHMENT
+                        | wgpu::TextureUsages::TEXTURE_BINDING
+                        | wgpu::TextureUsages::COPY_SRC,
+                    view_formats: &[],
+                })
+                .create_view(&wgpu::TextureViewDescriptor::default())
+        };
+        Target {
+            views: [create_view(), create_view()],
+            front: 0,
+        }
+    }
+
+    pub fn front(&self) -> &TextureView {
+        &self.views[self.front]
+    }
+
+    pub fn back(&self) -> &TextureView {
+        &self.views[1 - self.front]
+    }
+
+    pub fn swap(&mut self) {
+        self.front = 1 - self.front;
+    }
+}
@@ -1,25 +1,245 @@
-use iced_wgpu::wgpu::{Device, RenderPass};
+use iced_wgpu::wgpu::{self, BindGroupLayout, CommandEncoder, Device, Queue, TextureView};
 
+pub mod channels;
+pub mod compute;
+pub mod globals;
+pub mod graph;
+pub mod offscreen;
 pub mod rectangle;
 pub mod rectangle_pipeline;
 
+use channels::{Channel, Inputs, Source};
+use compute::{ComputeData, Dispatch, Storage};
+use globals::GlobalsBuffer;
+use graph::{PASS_COUNT, Pass, Target};
 use rectangle::Rectangle;
+use rectangle_pipeline::PipelineData;
 
+use crate::language::Shader;
+use crate::prelude::Composed;
+
+/// Buffer targets and storage of a size, the window has its own ones
+/// and the offscreen renderings get theirs from [`Scene::create_targets`]
+pub struct Targets {
+    buffers: Vec<Target>,
+    storage: Storage,
+    width: u32,
+    height: u32,
+}
+
+/// Render graph of the compute pass, the buffer passes and the image pass
 pub struct Scene {
     rectangle: Rectangle,
+    pub globals: GlobalsBuffer,
+    channels_layout: BindGroupLayout,
+    placeholder: TextureView,
+    /// Passes without a pipeline are skipped
+    pipelines: [Option<PipelineData>; PASS_COUNT],
+    inputs: [Inputs; PASS_COUNT],
+    /// Outputs of the buffer passes
+    targets: Vec<Target>,
+    compute: Option<ComputeData>,
+    storage: Storage,
+    dispatch: Dispatch,
+    width: u32,
+    height: u32,
 }
 
 impl Scene {
-    pub fn new(device: &Device) -> Self {
+    pub fn new(device: &Device, queue: &Queue, width: u32, height: u32) -> Self {
         let rectangle = Rectangle::new(device);
-        Scene { rectangle }
+        let globals = GlobalsBuffer::new(device);
+        let channels_layout = channels::create_bind_group_layout(device);
+        let placeholder = channels::create_placeholder(device, queue);
+        let targets = create_targets(device, width, height);
+        Scene {
+            rectangle,
+            globals,
+            channels_layout,
+            placeholder,
+            pipelines: Default::default(),
+            inputs: std::array::from_fn(|_| Inputs::new(device)),
+            targets,
+            compute: None,
+            storage: Storage::new(device, width, height),
+            dispatch: Dispatch::default(),
+            width,
+            height,
+        }
+    }
+
+    /// Layouts of the bind groups set by [`Scene::render`], in group order
+    pub fn bind_group_layouts(&self) -> [&BindGroupLayout; 2] {
+        [&self.globals.bind_group_layout, &self.channels_layout]
+    }
+
+    /// Layouts of the compute pass, the storage is bound to group 2
+    pub fn compute_bind_group_layouts(&self) -> [&BindGroupLayout; 3] {
+        [
+            &self.globals.bind_group_layout,
+            &self.channels_layout,
+            &self.storage.bind_group_layout,
+        ]
+    }
+
+    pub fn set_compute(&mut self, compute: Option<ComputeData>) {
+        self.compute = compute;
+    }
+
+    pub fn set_dispatch(&mut self, dispatch: Dispatch) {
+        self.dispatch = dispatch;
+    }
+
+    pub fn set_pipeline(&mut self, pass: Pass, pipeline: Option<PipelineData>) {
+        self.pipelines[pass.index()] = pipeline;
+    }
+
+    pub fn set_channel(
+        &mut self,
+        device: &Device,
+        queue: &Queue,
+        pass: Pass,
+        index: usize,
+        channel: &Channel,
+    ) -> Result<(), String> {
+        if pass == Pass::Compute && matches!(channel.source, Source::Storage(_)) {
+            return Err("The compute pass can't read the storage textures it writes".to_owned());
+        }
+        self.inputs[pass.index()].set(device, queue, index, channel)
+    }
+
+    /// Buffers follow the size of the window, their content is cleared
+    pub fn resize(&mut self, device: &Device, width: u32, height: u32) {
+        for target in &mut self.targets {
+            *target = Target::new(device, width, height);
+        }
+        self.storage.resize(device, width, height);
+        self.width = width;
+        self.height = height;
+    }
+
+    /// Empty buffers and storage of the size, for [`Scene::swap_targets`]
+    pub fn create_targets(&self, device: &Device, width: u32, height: u32) -> Targets {
+        Targets {
+            buffers: create_targets(device, width, height),
+            storage: self.storage.new_like(device, width, height),
+            width,
+            height,
+        }
+    }
+
+    /// Renders into `targets` from now on, they get the current ones
+    pub fn swap_targets(&mut self, targets: &mut Targets) {
+        std::mem::swap(&mut self.targets, &mut targets.buffers);
+        std::mem::swap(&mut self.storage, &mut targets.storage);
+        std::mem::swap(&mut self.width, &mut targets.width);
+        std::mem::swap(&mut self.height, &mut targets.height);
+    }
+
+    fn resolve(&self, source: &Source) -> Option<&TextureView> {
+        match source {
+            Source::Buffer(pass) if pass.is_buffer() => Some(self.targets[pass.index()].front()),
+            Source::Storage(index) => Some(self.storage.view(*index)),
+            _ => None,
+        }
+    }
+
+    fn create_bind_group(&self, device: &Device, pass: Pass) -> wgpu::BindGroup {
+        self.inputs[pass.index()].create_bind_group(
+            device,
+            &self.channels_layout,
+            &self.placeholder,
+            |source| self.resolve(source),
+        )
+    }
+
+    fn run_compute(&self, device: &Device, encoder: &mut CommandEncoder) {
+        let Some(compute) = &self.compute else {
+            return;
+        };
+        let bind_group = self.create_bind_group(device, Pass::Compute);
+        let mut compute_pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+            label: None,
+            timestamp_writes: None,
+        });
+        compute_pass.set_bind_group(0, &self.globals.bind_group, &[]);
+        compute_pass.set_bind_group(1, &bind_group, &[]);
+        compute_pass.set_bind_group(2, &self.storage.bind_group, &[]);
+        compute.dispatch(&mut compute_pass, self.dispatch, self.width, self.height);
     }
 
-    pub fn render<'a>(
-        &'a self,
-        rectangle_pipeline_data: &'a rectangle_pipeline::PipelineData,
-        pass: &mut RenderPass<'a>,
-    ) {
-        self.rectangle.render(rectangle_pipeline_data, pass);
+    pub fn render(&mut self, device: &Device, encoder: &mut CommandEncoder, view: &TextureView) {
+        for pass in Pass::ALL {
+            if pass == Pass::Compute {
+                self.run_compute(device, encoder);
+                continue;
+            }
+            let pipeline = self.pipelines[pass.index()].as_ref();
+            if pipeline.is_none() && pass.is_buffer() {
+                continue;
+            }
+
+            let bind_group = self.create_bind_group(device, pass);
+            let target = if pass.is_buffer() {
+                self.targets[pass.index()].back()
+            } else {
+                view
+            };
+
+            {
+                let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                    label: None,
+                    color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                        view: target,
+                        depth_slice: None,
+                        resolve_target: None,
+                        ops: wgpu::Operations {
+                            load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                            store: wgpu::StoreOp::Store,
+                        },
+                    })],
+                    depth_stencil_attachment: None,
+                    timestamp_writes: None,
+                    occlusion_query_set: None,
+                });
+
+                if let Some(pipeline) = pipeline {
+                    render_pass.set_bind_group(0, &self.globals.bind_group, &[]);
+                    render_pass.set_bind_group(1, &bind_group, &[]);
+                    if pipeline.fragment_only {
+                        render_pass.set_pipeline(&pipeline.pipeline);
+                        render_pass.draw(0..3, 0..1);
+                    } else {
+                        self.rectangle.render(pipeline, &mut render_pass);
+                    }
+                }
+            }
+
+            if pass.is_buffer() {
+                self.targets[pass.index()].swap();
+            }
+        }
+    }
+}
+
+fn create_targets(device: &Device, width: u32, height: u32) -> Vec<Target> {
+    Pass::BUFFERS
+        .iter()
+        .map(|_| Target::new(device, width, height))
+        .collect()
+}
+
+/// Source of the shader module, WGSL is passed to wgpu as text
+/// and the other languages as the naga IR of their front ends
+pub fn shader_source(
+    shader: &Shader,
+    module: naga::Module,
+    composed: &Composed,
+) -> wgpu::ShaderSource<'static> {
+    match shader {
+        Shader::Wgsl(_) => wgpu::ShaderSource::Wgsl(composed.source.clone().into()),
+        Shader::Glsl(..) | Shader::SpirV(_) => {
+            wgpu::ShaderSource::Naga(std::borrow::Cow::Owned(module))
+        }
     }
 }
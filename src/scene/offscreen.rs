@@ -0,0 +1,124 @@
+use iced_wgpu::wgpu::{self, Device, Queue, Texture, TextureFormat, TextureView};
+use image::RgbaImage;
+
+/// Render target outside of the window, copied back to the CPU after rendering
+pub struct Offscreen {
+    texture: Texture,
+    view: TextureView,
+}
+
+impl Offscreen {
+    /// `format` should match the image pass pipeline, only 8-bit RGBA and BGRA can be read back
+    pub fn new(
+        device: &Device,
+        format: TextureFormat,
+        width: u32,
+        height: u32,
+    ) -> Result<Self, String> {
+        let max = device.limits().max_texture_dimension_2d;
+        if width == 0 || height == 0 || width > max || height > max {
+            return Err(format!(
+                "Image size {width}x{height} is outside of 1x1..{max}x{max}"
+            ));
+        }
+        if !matches!(
+            format,
+            TextureFormat::Rgba8Unorm
+                | TextureFormat::Rgba8UnormSrgb
+                | TextureFormat::Bgra8Unorm
+                | TextureFormat::Bgra8UnormSrgb
+        ) {
+            return Err(format!("Can't read back the {format:?} format"));
+        }
+
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Texture - Offscreen"),
+            size: wgpu::Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+            view_formats: &[],
+        });
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        Ok(Offscreen { texture, view })
+    }
+
+    pub fn view(&self) -> &TextureView {
+        &self.view
+    }
+
+    /// Waits for the rendering to finish and copies the texture into an opaque image,
+    /// the same way it's shown in the window
+    pub fn read(&self, device: &Device, queue: &Queue) -> Result<RgbaImage, String> {
+        let width = self.texture.width();
+        let height = self.texture.height();
+        let row_length = width * 4;
+        // rows of the copy are aligned in the buffer
+        let padded_row_length = row_length.next_multiple_of(wgpu::COPY_BYTES_PER_ROW_ALIGNMENT);
+
+        let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Buffer - Offscreen"),
+            size: u64::from(padded_row_length) * u64::from(height),
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+
+        let mut encoder =
+            device.create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
+        encoder.copy_texture_to_buffer(
+            self.texture.as_image_copy(),
+            wgpu::TexelCopyBufferInfo {
+                buffer: &buffer,
+                layout: wgpu::TexelCopyBufferLayout {
+                    offset: 0,
+                    bytes_per_row: Some(padded_row_length),
+                    rows_per_image: None,
+                },
+            },
+            self.texture.size(),
+        );
+        queue.submit([encoder.finish()]);
+
+        let slice = buffer.slice(..);
+        let (sender, receiver) = std::sync::mpsc::channel();
+        slice.map_async(wgpu::MapMode::Read, move |result| {
+            let _ = sender.send(result);
+        });
+        device
+            .poll(wgpu::PollType::wait_indefinitely())
+            .map_err(|e| e.to_string())?;
+        receiver
+            .recv()
+            .map_err(|e| e.to_string())?
+            .map_err(|e| e.to_string())?;
+
+        let mut pixels = Vec::with_capacity((row_length * height) as usize);
+        {
+            let data = slice.get_mapped_range();
+            for row in data.chunks(padded_row_length as usize) {
+                pixels.extend_from_slice(&row[..row_length as usize]);
+            }
+        }
+        buffer.unmap();
+
+        let bgra = matches!(
+            self.texture.format(),
+            TextureFormat::Bgra8Unorm | TextureFormat::Bgra8UnormSrgb
+        );
+        for pixel in pixels.chunks_exact_mut(4) {
+            if bgra {
+                pixel.swap(0, 2);
+            }
+            pixel[3] = u8::MAX;
+        }
+
+        RgbaImage::from_raw(width, height, pixels)
+            .ok_or_else(|| "Read back fewer pixels than expected".to_owned())
+    }
+}
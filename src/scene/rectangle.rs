@@ -16,9 +16,12 @@ fn vertex(x: f32, y: f32) -> TrianglePoint {
     }
 }
 
+/// Corners of the rectangle covering the render target, in clip space
+pub const POSITIONS: [[f32; 2]; 4] = [[1.0, -1.0], [-1.0, 1.0], [-1.0, -1.0], [1.0, 1.0]];
+/// Two counter-clockwise triangles
+pub const INDICES: [u16; 6] = [0, 1, 2, 0, 3, 1];
+
 pub struct Rectangle {
-    pub vertices: Vec<TrianglePoint>,
-    pub indices: Vec<u16>,
     pub vertex_buf: wgpu::Buffer,
     pub index_buf: wgpu::Buffer,
     pub index_count: usize,
@@ -26,17 +29,8 @@ pub struct Rectangle {
 
 impl Rectangle {
     pub fn new(device: &Device) -> Self {
-        let vertex_data = vec![
-            vertex(1.0, -1.0),
-            vertex(-1.0, 1.0),
-            vertex(-1.0, -1.0),
-            vertex(1.0, 1.0),
-        ];
-        #[rustfmt::skip]
-        let index_data = vec![
-            0, 1, 2,
-            0, 3, 1,
-        ];
+        let vertex_data: Vec<_> = POSITIONS.iter().map(|&[x, y]| vertex(x, y)).collect();
+        let index_data = INDICES.to_vec();
 
         let vertex_buf = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
             label: Some("Vertex Buffer - Rectangle"),
@@ -53,8 +47,6 @@ impl Rectangle {
         let index_count = index_data.len();
 
         Rectangle {
-            vertices: vertex_data,
-            indices: index_data,
             vertex_buf,
             index_buf,
             index_count,
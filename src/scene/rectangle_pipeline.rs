@@ -1,34 +1,73 @@
 use iced_wgpu::wgpu::{
-    self, Device, PipelineLayout, PipelineLayoutDescriptor, RenderPipeline,
-    RenderPipelineDescriptor, SurfaceConfiguration,
+    self, BindGroupLayout, Device, PipelineLayout, PipelineLayoutDescriptor, RenderPipeline,
+    RenderPipelineDescriptor, TextureFormat,
 };
 
 use super::rectangle::TrianglePoint;
-use crate::SHADER_FILE;
+use crate::entry_points::Selection;
+use crate::language::Shader;
+use crate::validator::Validation;
+use crate::{prelude, validator};
 
 pub struct PipelineData {
-    pub pipeline_layout: PipelineLayout,
     pub pipeline: RenderPipeline,
+    /// Drawn with the built-in full-screen triangle instead of the rectangle
+    pub fragment_only: bool,
 }
 
 const VERTEX_SIZE: usize = std::mem::size_of::<TrianglePoint>();
 
 impl PipelineData {
-    pub fn new(device: &Device, config: &SurfaceConfiguration) -> Self {
-        let shader_text = std::fs::read_to_string(SHADER_FILE).expect("Should read the shader");
-
-        Self::new_with_text(device, config, &shader_text)
+    pub fn new_with_shader(
+        device: &Device,
+        format: TextureFormat,
+        bind_group_layouts: &[&BindGroupLayout],
+        shader: &Shader,
+        selection: &Selection,
+    ) -> Result<Self, String> {
+        let validation = validator::validate(shader).map_err(|diagnostic| diagnostic.message)?;
+        Self::new_with_validation(
+            device,
+            format,
+            bind_group_layouts,
+            shader,
+            &validation,
+            selection,
+        )
     }
 
-    pub fn new_with_text(device: &Device, config: &SurfaceConfiguration, text: &str) -> Self {
-        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+    /// Uses the module of a validation done before, e.g. in the background
+    pub fn new_with_validation(
+        device: &Device,
+        format: TextureFormat,
+        bind_group_layouts: &[&BindGroupLayout],
+        shader: &Shader,
+        validation: &Validation,
+        selection: &Selection,
+    ) -> Result<Self, String> {
+        let composed = &validation.composed;
+        let fragment_only = composed.fragment_only;
+
+        // naga accepts more than the backend and the pipeline layout do,
+        // catch the rest here instead of the uncaptured error handler
+        device.push_error_scope(wgpu::ErrorFilter::Validation);
+
+        let shader_module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
             label: None,
-            source: wgpu::ShaderSource::Wgsl(std::borrow::Cow::Borrowed(text)),
+            source: super::shader_source(shader, validation.module.clone(), composed),
+        });
+        // the full-screen triangle is appended to WGSL,
+        // the other languages get it as a separate module
+        let vertex_module = (fragment_only && !matches!(shader, Shader::Wgsl(_))).then(|| {
+            device.create_shader_module(wgpu::ShaderModuleDescriptor {
+                label: None,
+                source: wgpu::ShaderSource::Wgsl(prelude::FULLSCREEN_WGSL.into()),
+            })
         });
 
         let pipeline_layout = device.create_pipeline_layout(&PipelineLayoutDescriptor {
             label: None,
-            bind_group_layouts: &[],
+            bind_group_layouts,
             push_constant_ranges: &[],
         });
 
@@ -42,35 +81,51 @@ impl PipelineData {
             }],
         }];
 
-        let pipeline =
-            Self::create_pipeline(device, config, &shader, &pipeline_layout, &vertex_buffers);
-        PipelineData {
-            pipeline_layout,
-            pipeline,
+        // the full-screen triangle is generated from the vertex index
+        let vertex_buffers: &[wgpu::VertexBufferLayout] =
+            if fragment_only { &[] } else { &vertex_buffers };
+        let pipeline = Self::create_pipeline(
+            device,
+            format,
+            vertex_module.as_ref().unwrap_or(&shader_module),
+            &shader_module,
+            &pipeline_layout,
+            vertex_buffers,
+            selection,
+        );
+
+        if let Some(error) = futures::executor::block_on(device.pop_error_scope()) {
+            return Err(error.to_string());
         }
+        Ok(PipelineData {
+            pipeline,
+            fragment_only,
+        })
     }
 
     fn create_pipeline(
         device: &Device,
-        config: &SurfaceConfiguration,
-        shader: &wgpu::ShaderModule,
+        format: TextureFormat,
+        vertex_module: &wgpu::ShaderModule,
+        fragment_module: &wgpu::ShaderModule,
         pipeline_layout: &PipelineLayout,
         vertex_buffers: &[wgpu::VertexBufferLayout],
+        selection: &Selection,
     ) -> RenderPipeline {
         device.create_render_pipeline(&RenderPipelineDescriptor {
             label: None,
             layout: Some(pipeline_layout),
             vertex: wgpu::VertexState {
-                module: shader,
-                entry_point: Some("vs_main"),
+                module: vertex_module,
+                entry_point: selection.vertex.as_deref(),
                 compilation_options: Default::default(),
                 buffers: vertex_buffers,
             },
             fragment: Some(wgpu::FragmentState {
-                module: shader,
-                entry_point: Some("fs_main"),
+                module: fragment_module,
+                entry_point: selection.fragment.as_deref(),
                 compilation_options: Default::default(),
-                targets: &[Some(config.view_formats[0].into())],
+                targets: &[Some(format.into())],
             }),
             primitive: wgpu::PrimitiveState {
                 cull_mode: Some(wgpu::Face::Back),
@@ -0,0 +1,455 @@
+//! Imports Shadertoy shaders, either a `mainImage` snippet or a JSON export with all passes.
+//!
+//! Every pass becomes a GLSL fragment shader: the code is wrapped with the Shadertoy inputs
+//! mapped onto the built-in uniforms and channels, and with a `main` calling `mainImage`.
+
+use std::path::Path;
+
+use serde::Deserialize;
+
+use crate::cli::ImportArgs;
+use crate::scene::channels::{CHANNEL_COUNT, Channel, Filter, Source, Wrap};
+use crate::scene::graph::Pass;
+
+/// Extensions listed by the import file browser, other files are read as snippets too
+pub const EXTENSIONS: [&str; 2] = ["json", "glsl"];
+
+/// Declarations prepended to the Shadertoy code. The helpers are defined before the
+/// macros, so they still see the real `iChannel0` textures and `texture` functions.
+const WRAPPER_HEADER: &str = "\
+// Shadertoy inputs, set by main() from the built-in uniforms
+vec3 iResolution;
+float iTime;
+float iTimeDelta;
+float iFrameRate;
+int iFrame;
+vec4 iMouse;
+vec4 iDate;
+float iChannelTime[4];
+vec3 iChannelResolution[4];
+float iSampleRate;
+
+layout(location = 0) out vec4 shadertoy_color;
+
+// Shadertoy has the origin at the bottom left corner, the editor at the top left,
+// so the lookups are flipped to keep the buffers and the images upright
+vec4 shadertoy_texture(int channel, vec2 uv, float bias) {
+    uv.y = 1.0 - uv.y;
+    if (channel == 0) return texture(sampler2D(iChannel0, iSampler0), uv, bias);
+    if (channel == 1) return texture(sampler2D(iChannel1, iSampler1), uv, bias);
+    if (channel == 2) return texture(sampler2D(iChannel2, iSampler2), uv, bias);
+    return texture(sampler2D(iChannel3, iSampler3), uv, bias);
+}
+
+vec4 shadertoy_texture(int channel, vec2 uv) {
+    return shadertoy_texture(channel, uv, 0.0);
+}
+
+vec4 shadertoy_textureLod(int channel, vec2 uv, float lod) {
+    uv.y = 1.0 - uv.y;
+    if (channel == 0) return textureLod(sampler2D(iChannel0, iSampler0), uv, lod);
+    if (channel == 1) return textureLod(sampler2D(iChannel1, iSampler1), uv, lod);
+    if (channel == 2) return textureLod(sampler2D(iChannel2, iSampler2), uv, lod);
+    return textureLod(sampler2D(iChannel3, iSampler3), uv, lod);
+}
+
+ivec2 shadertoy_textureSize(int channel, int lod) {
+    if (channel == 0) return textureSize(sampler2D(iChannel0, iSampler0), lod);
+    if (channel == 1) return textureSize(sampler2D(iChannel1, iSampler1), lod);
+    if (channel == 2) return textureSize(sampler2D(iChannel2, iSampler2), lod);
+    return textureSize(sampler2D(iChannel3, iSampler3), lod);
+}
+
+vec4 shadertoy_texelFetch(int channel, ivec2 texel, int lod) {
+    texel.y = shadertoy_textureSize(channel, lod).y - 1 - texel.y;
+    if (channel == 0) return texelFetch(sampler2D(iChannel0, iSampler0), texel, lod);
+    if (channel == 1) return texelFetch(sampler2D(iChannel1, iSampler1), texel, lod);
+    if (channel == 2) return texelFetch(sampler2D(iChannel2, iSampler2), texel, lod);
+    return texelFetch(sampler2D(iChannel3, iSampler3), texel, lod);
+}
+
+// the channels are passed around as indices
+#define iChannel0 0
+#define iChannel1 1
+#define iChannel2 2
+#define iChannel3 3
+#define sampler2D int
+#define texture shadertoy_texture
+#define textureLod shadertoy_textureLod
+#define textureSize shadertoy_textureSize
+#define texelFetch shadertoy_texelFetch
+";
+
+/// Converts the built-in uniforms to the Shadertoy conventions and calls `mainImage`
+const WRAPPER_MAIN: &str = "\
+// y of a position from the editor, zero stays zero and the sign is kept
+float shadertoy_flip(float y) {
+    return y == 0.0 ? 0.0 : sign(y) * (globals.resolution.y - abs(y));
+}
+
+void main() {
+    iResolution = vec3(globals.resolution, 1.0);
+    iTime = globals.time;
+    iTimeDelta = globals.time_delta;
+    iFrameRate = globals.time_delta > 0.0 ? 1.0 / globals.time_delta : 60.0;
+    iFrame = int(globals.frame);
+    iMouse = vec4(
+        globals.mouse.x,
+        shadertoy_flip(globals.mouse.y),
+        globals.mouse.z,
+        shadertoy_flip(globals.mouse.w)
+    );
+    iDate = globals.date;
+    iSampleRate = 44100.0;
+    for (int channel = 0; channel < 4; channel++) {
+        iChannelTime[channel] = globals.time;
+    }
+";
+
+/// Only the shaders using it query the textures, the CPU renderer has no textures
+const WRAPPER_CHANNEL_RESOLUTION: &str = "\
+    for (int channel = 0; channel < 4; channel++) {
+        iChannelResolution[channel] = vec3(vec2(textureSize(channel, 0)), 1.0);
+    }
+";
+
+/// Shadertoy pixel coordinates have the origin at the bottom left corner
+const WRAPPER_CALL: &str = "
+    vec4 color = vec4(0.0, 0.0, 0.0, 1.0);
+    mainImage(color, vec2(gl_FragCoord.x, globals.resolution.y - gl_FragCoord.y));
+";
+
+/// A Shadertoy pass converted to a GLSL fragment shader
+#[derive(Debug)]
+pub struct ImportedPass {
+    pub pass: Pass,
+    /// File name without the extension, like `buffer_a`
+    pub name: String,
+    /// GLSL fragment shader with the wrapper
+    pub source: String,
+    pub channels: [Channel; CHANNEL_COUNT],
+}
+
+#[derive(Debug)]
+pub struct Import {
+    pub name: String,
+    /// Passes in the render order, the image pass is the last one
+    pub passes: Vec<ImportedPass>,
+    /// Inputs and passes which have no counterpart in the editor
+    pub warnings: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum Export {
+    /// Response of the Shadertoy API
+    Api {
+        #[serde(rename = "Shader")]
+        shader: Effect,
+    },
+    Effect(Effect),
+    /// Export of several shaders, only the first one is imported
+    List(Vec<Export>),
+}
+
+#[derive(Debug, Deserialize)]
+struct Effect {
+    #[serde(default)]
+    info: Info,
+    renderpass: Vec<RenderPass>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct Info {
+    #[serde(default)]
+    name: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct RenderPass {
+    #[serde(default)]
+    inputs: Vec<Input>,
+    #[serde(default)]
+    outputs: Vec<Output>,
+    code: String,
+    #[serde(default)]
+    name: String,
+    #[serde(rename = "type")]
+    kind: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct Input {
+    #[serde(default)]
+    id: serde_json::Value,
+    /// `filepath` and `type` in the older exports
+    #[serde(default, alias = "filepath")]
+    src: String,
+    #[serde(alias = "type")]
+    ctype: String,
+    channel: usize,
+    #[serde(default)]
+    sampler: Sampler,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct Sampler {
+    #[serde(default)]
+    filter: String,
+    #[serde(default)]
+    wrap: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct Output {
+    id: serde_json::Value,
+}
+
+/// Writes the passes as `.frag` files and prints the channels to bind,
+/// a single image pass gets the name of the input
+pub fn run(args: &ImportArgs) -> Result<bool, String> {
+    let import = import(&args.input)?;
+    let stem = args
+        .input
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "shadertoy".to_owned());
+    std::fs::create_dir_all(&args.output)
+        .map_err(|e| format!("Error creating {}: {e}", args.output.display()))?;
+
+    let is_single = import.passes.len() == 1;
+    for imported in &import.passes {
+        let file_name = if is_single {
+            format!("{stem}.frag")
+        } else {
+            format!("{stem}_{}.frag", imported.name)
+        };
+        let path = args.output.join(file_name);
+        std::fs::write(&path, &imported.source)
+            .map_err(|e| format!("Error writing {}: {e}", path.display()))?;
+        println!("{}: {}", imported.pass, path.display());
+        for (index, channel) in imported.channels.iter().enumerate() {
+            if channel.source != Source::None {
+                println!(
+                    "  iChannel{index}: {} ({}, {})",
+                    channel.source, channel.filter, channel.wrap
+                );
+            }
+        }
+    }
+    for warning in &import.warnings {
+        println!("warning: {warning}");
+    }
+    Ok(true)
+}
+
+/// Reads a JSON export, or a `mainImage` snippet from any other file
+pub fn import(path: &Path) -> Result<Import, String> {
+    let text = std::fs::read_to_string(path)
+        .map_err(|e| format!("Error reading {}: {e}", path.display()))?;
+    let is_json = path
+        .extension()
+        .is_some_and(|extension| extension.eq_ignore_ascii_case("json"));
+    let stem = path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    if !is_json {
+        return Ok(Import {
+            name: stem,
+            passes: vec![ImportedPass {
+                pass: Pass::Image,
+                name: "image".to_owned(),
+                source: wrap("", &text, Pass::Image),
+                channels: Default::default(),
+            }],
+            warnings: Vec::new(),
+        });
+    }
+
+    let export: Export = serde_json::from_str(&text).map_err(|e| match e.classify() {
+        // the untagged enum doesn't tell which field is missing
+        serde_json::error::Category::Data => {
+            format!(
+                "{} is not a Shadertoy export, it has no renderpass list",
+                path.display()
+            )
+        }
+        _ => format!("Error parsing {}: {e}", path.display()),
+    })?;
+    let directory = path.parent().unwrap_or(Path::new("."));
+    let mut import = import_export(export, directory)?;
+    if import.name.is_empty() {
+        import.name = stem;
+    }
+    Ok(import)
+}
+
+fn import_export(export: Export, directory: &Path) -> Result<Import, String> {
+    let effect = match export {
+        Export::Api { shader } => shader,
+        Export::Effect(effect) => effect,
+        Export::List(exports) => match exports.into_iter().next() {
+            Some(export) => return import_export(export, directory),
+            None => return Err("The export has no shaders".to_owned()),
+        },
+    };
+
+    let mut warnings = Vec::new();
+    let common = effect
+        .renderpass
+        .iter()
+        .filter(|render_pass| render_pass.kind == "common")
+        .map(|render_pass| render_pass.code.as_str())
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    // buffers are matched by the name, or take the free letters in order
+    let mut passes: Vec<(Pass, &RenderPass)> = Vec::new();
+    for render_pass in &effect.renderpass {
+        let is_free = |pass: &Pass| passes.iter().all(|(taken, _)| taken != pass);
+        let pass = match render_pass.kind.as_str() {
+            "image" => Some(Pass::Image).filter(is_free),
+            "buffer" => Pass::BUFFERS
+                .into_iter()
+                .find(|pass| {
+                    is_free(pass) && pass.to_string().eq_ignore_ascii_case(&render_pass.name)
+                })
+                .or_else(|| Pass::BUFFERS.into_iter().find(is_free)),
+            "common" => continue,
+            kind => {
+                warnings.push(format!(
+                    "{} was skipped, {kind} passes are not supported",
+                    render_pass.name
+                ));
+                continue;
+            }
+        };
+        let Some(pass) = pass else {
+            warnings.push(format!(
+                "{} was skipped, the pass is taken",
+                render_pass.name
+            ));
+            continue;
+        };
+        passes.push((pass, render_pass));
+    }
+    passes.sort_by_key(|(pass, _)| Pass::ALL.iter().position(|other| other == pass));
+    if !passes.iter().any(|(pass, _)| *pass == Pass::Image) {
+        return Err("The export has no image pass".to_owned());
+    }
+
+    let imported = passes
+        .iter()
+        .map(|(pass, render_pass)| {
+            let mut channels: [Channel; CHANNEL_COUNT] = Default::default();
+            for input in &render_pass.inputs {
+                let Some(channel) = channels.get_mut(input.channel) else {
+                    continue;
+                };
+                let source = input_source(input, &passes, directory);
+                if let Err(e) = &source {
+                    warnings.push(format!("iChannel{} of {pass}: {e}", input.channel));
+                }
+                *channel = Channel {
+                    source: source.unwrap_or_default(),
+                    filter: match input.sampler.filter.as_str() {
+                        "nearest" => Filter::Nearest,
+                        _ => Filter::Linear,
+                    },
+                    wrap: match input.sampler.wrap.as_str() {
+                        "clamp" => Wrap::Clamp,
+                        _ => Wrap::Repeat,
+                    },
+                };
+            }
+            ImportedPass {
+                pass: *pass,
+                name: pass.to_string().to_ascii_lowercase().replace(' ', "_"),
+                source: wrap(&common, &render_pass.code, *pass),
+                channels,
+            }
+        })
+        .collect();
+
+    Ok(Import {
+        name: effect.info.name,
+        passes: imported,
+        warnings,
+    })
+}
+
+/// Buffers are found by the output id, the textures in the directory of the export
+fn input_source(
+    input: &Input,
+    passes: &[(Pass, &RenderPass)],
+    directory: &Path,
+) -> Result<Source, String> {
+    match input.ctype.as_str() {
+        "buffer" => {
+            let by_id = passes.iter().find(|(_, render_pass)| {
+                render_pass
+                    .outputs
+                    .iter()
+                    .any(|output| output.id == input.id)
+            });
+            // previews of the buffers are named buffer00.png to buffer03.png
+            let by_src = Pass::BUFFERS
+                .into_iter()
+                .enumerate()
+                .find(|(index, _)| input.src.ends_with(&format!("buffer{index:02}.png")));
+            by_id
+                .map(|(pass, _)| *pass)
+                .or(by_src.map(|(_, pass)| pass))
+                .filter(|pass| pass.is_buffer())
+                .map(Source::Buffer)
+                .ok_or_else(|| format!("buffer {} is not in the export", input.id))
+        }
+        "texture" => {
+            let relative = input.src.trim_start_matches('/');
+            let candidates = [
+                directory.join(relative),
+                directory.join(Path::new(relative).file_name().unwrap_or_default()),
+            ];
+            candidates
+                .into_iter()
+                .find(|path| path.is_file())
+                .map(Source::Image)
+                .ok_or_else(|| format!("texture {} is not next to the export", input.src))
+        }
+        ctype => Err(format!("{ctype} inputs are not supported")),
+    }
+}
+
+/// GLSL fragment shader running the `mainImage` of the Shadertoy `code`,
+/// the image pass ignores the alpha the same way Shadertoy does
+pub fn wrap(common: &str, code: &str, pass: Pass) -> String {
+    let output = if pass == Pass::Image {
+        "vec4(color.rgb, 1.0)"
+    } else {
+        "color"
+    };
+    let mut source = String::from(WRAPPER_HEADER);
+    if !common.trim().is_empty() {
+        source.push_str("\n// Common\n");
+        source.push_str(common.trim_end());
+        source.push('\n');
+    }
+    source.push('\n');
+    source.push_str(code.trim_end());
+    source.push_str("\n\n");
+    source.push_str(WRAPPER_MAIN);
+    if common.contains("iChannelResolution") || code.contains("iChannelResolution") {
+        source.push_str(WRAPPER_CHANNEL_RESOLUTION);
+    }
+    source.push_str(WRAPPER_CALL);
+    source.push_str(&format!("    shadertoy_color = {output};\n}}\n"));
+    source
+}
+
+/// Lists the files in the import file browser
+pub fn is_importable(path: &Path) -> bool {
+    path.extension()
+        .and_then(|extension| extension.to_str())
+        .is_some_and(|extension| EXTENSIONS.contains(&extension.to_ascii_lowercase().as_str()))
+}
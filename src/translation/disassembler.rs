@@ -0,0 +1,516 @@
+//! Text form of a SPIR-V module, close to the output of `spirv-dis --raw-id`.
+//!
+//! Only the operands of the instructions emitted by naga are decoded,
+//! the rest are printed as ids.
+
+use std::collections::HashSet;
+use std::fmt::Write;
+
+use spirv::Op;
+
+/// Kind of an instruction operand
+#[derive(Clone, Copy)]
+enum Operand {
+    Id,
+    Literal,
+    String,
+    Capability,
+    ExecutionModel,
+    ExecutionMode,
+    AddressingModel,
+    MemoryModel,
+    StorageClass,
+    Decoration,
+    Dim,
+    ImageFormat,
+    SourceLanguage,
+    FunctionControl,
+    SelectionControl,
+    LoopControl,
+    /// Followed by the alignment when it's `Aligned`
+    MemoryAccess,
+    /// Followed by the ids of the operands
+    ImageOperands,
+    /// Instruction number of the extended instruction set
+    ExtInst,
+    /// Literal and label pairs of `OpSwitch`
+    SwitchTarget,
+}
+
+/// Operands of an instruction after the result type and id,
+/// the last kind repeats until the end of the instruction
+struct Layout {
+    has_type: bool,
+    has_result: bool,
+    operands: &'static [Operand],
+}
+
+pub fn disassemble(words: &[u32]) -> String {
+    let mut output = String::new();
+    if words.len() < 5 || words[0] != spirv::MAGIC_NUMBER {
+        return "; not a SPIR-V module".to_owned();
+    }
+    let version = words[1];
+    let _ = writeln!(output, "; SPIR-V");
+    let _ = writeln!(
+        output,
+        "; Version: {}.{}",
+        (version >> 16) & 0xff,
+        (version >> 8) & 0xff
+    );
+    let _ = writeln!(output, "; Generator: {:#010x}", words[2]);
+    let _ = writeln!(output, "; Bound: {}", words[3]);
+    let _ = writeln!(output, "; Schema: {}", words[4]);
+
+    let mut types = NumberTypes::default();
+    let mut offset = 5;
+    while offset < words.len() {
+        let count = (words[offset] >> 16) as usize;
+        let opcode = words[offset] & 0xffff;
+        if count == 0 || offset + count > words.len() {
+            let _ = writeln!(output, "; truncated instruction at word {offset}");
+            break;
+        }
+        instruction(
+            &mut output,
+            &mut types,
+            opcode,
+            &words[offset + 1..offset + count],
+        );
+        offset += count;
+    }
+    output
+}
+
+/// 32-bit scalar types, their constants are printed as numbers of the type
+#[derive(Default)]
+struct NumberTypes {
+    floats: HashSet<u32>,
+    signed: HashSet<u32>,
+}
+
+fn instruction(output: &mut String, types: &mut NumberTypes, opcode: u32, mut operands: &[u32]) {
+    let Some(op) = Op::from_u32(opcode) else {
+        let _ = writeln!(output, "{:15}OpUnknown{opcode} {operands:?}", "");
+        return;
+    };
+    let layout = layout(op);
+    let mut result_type = None;
+    if layout.has_type
+        && let Some((&id, rest)) = operands.split_first()
+    {
+        result_type = Some(id);
+        operands = rest;
+    }
+    let mut line = String::new();
+    if layout.has_result
+        && let Some((&id, rest)) = operands.split_first()
+    {
+        match (op, rest) {
+            (Op::TypeFloat, [32]) => {
+                types.floats.insert(id);
+            }
+            (Op::TypeInt, [32, 1]) => {
+                types.signed.insert(id);
+            }
+            _ => (),
+        }
+        if op == Op::Constant
+            && let (Some(ty), [bits]) = (result_type, rest)
+        {
+            let value = if types.floats.contains(&ty) {
+                f32::from_bits(*bits).to_string()
+            } else if types.signed.contains(&ty) {
+                (*bits as i32).to_string()
+            } else {
+                bits.to_string()
+            };
+            let _ = writeln!(
+                output,
+                "{:>15}OpConstant %{ty} {value}",
+                format!("%{id} = ")
+            );
+            return;
+        }
+        line = format!("%{id} = ");
+        operands = rest;
+    }
+    // results are right aligned, so the opcodes start in the same column
+    let _ = write!(output, "{line:>15}Op{op:?}");
+    if let Some(id) = result_type {
+        let _ = write!(output, " %{id}");
+    }
+
+    let mut kinds = layout.operands.iter().copied();
+    let mut kind = kinds.next().unwrap_or(Operand::Id);
+    let mut last_decoration = None;
+    while let Some((&word, rest)) = operands.split_first() {
+        operands = rest;
+        match kind {
+            Operand::Id => {
+                let _ = write!(output, " %{word}");
+            }
+            Operand::Literal => {
+                // the value of a built-in decoration is an enum
+                match (last_decoration.take(), spirv::BuiltIn::from_u32(word)) {
+                    (Some(spirv::Decoration::BuiltIn), Some(built_in)) => {
+                        let _ = write!(output, " {built_in:?}");
+                    }
+                    _ => {
+                        let _ = write!(output, " {word}");
+                    }
+                }
+            }
+            Operand::String => {
+                let (string, length) = string(word, operands);
+                operands = &operands[length..];
+                let _ = write!(output, " {string:?}");
+            }
+            Operand::Capability => enumerant(output, word, spirv::Capability::from_u32),
+            Operand::ExecutionModel => enumerant(output, word, spirv::ExecutionModel::from_u32),
+            Operand::ExecutionMode => enumerant(output, word, spirv::ExecutionMode::from_u32),
+            Operand::AddressingModel => enumerant(output, word, spirv::AddressingModel::from_u32),
+            Operand::MemoryModel => enumerant(output, word, spirv::MemoryModel::from_u32),
+            Operand::StorageClass => enumerant(output, word, spirv::StorageClass::from_u32),
+            Operand::Decoration => {
+                last_decoration = spirv::Decoration::from_u32(word);
+                enumerant(output, word, spirv::Decoration::from_u32);
+            }
+            Operand::Dim => enumerant(output, word, spirv::Dim::from_u32),
+            Operand::ImageFormat => enumerant(output, word, spirv::ImageFormat::from_u32),
+            Operand::SourceLanguage => enumerant(output, word, spirv::SourceLanguage::from_u32),
+            Operand::FunctionControl => {
+                mask(
+                    output,
+                    word,
+                    &[(1, "Inline"), (2, "DontInline"), (4, "Pure"), (8, "Const")],
+                );
+            }
+            Operand::SelectionControl => mask(output, word, &[(1, "Flatten"), (2, "DontFlatten")]),
+            Operand::LoopControl => mask(output, word, &[(1, "Unroll"), (2, "DontUnroll")]),
+            Operand::MemoryAccess => {
+                mask(
+                    output,
+                    word,
+                    &[(1, "Volatile"), (2, "Aligned"), (4, "Nontemporal")],
+                );
+            }
+            Operand::ImageOperands => mask(
+                output,
+                word,
+                &[
+                    (0x1, "Bias"),
+                    (0x2, "Lod"),
+                    (0x4, "Grad"),
+                    (0x8, "ConstOffset"),
+                    (0x10, "Offset"),
+                    (0x20, "ConstOffsets"),
+                    (0x40, "Sample"),
+                    (0x80, "MinLod"),
+                ],
+            ),
+            Operand::ExtInst => enumerant(output, word, spirv::GLOp::from_u32),
+            Operand::SwitchTarget => match operands.split_first() {
+                Some((&label, rest)) => {
+                    operands = rest;
+                    let _ = write!(output, " {word} %{label}");
+                }
+                None => {
+                    let _ = write!(output, " {word}");
+                }
+            },
+        }
+        if let Some(next) = kinds.next() {
+            kind = next;
+        }
+    }
+    output.push('\n');
+}
+
+fn enumerant<T: std::fmt::Debug>(output: &mut String, word: u32, from_u32: fn(u32) -> Option<T>) {
+    let _ = match from_u32(word) {
+        Some(value) => write!(output, " {value:?}"),
+        None => write!(output, " {word}"),
+    };
+}
+
+/// Names of the set bits joined by `|`, `None` without any
+fn mask(output: &mut String, word: u32, names: &[(u32, &str)]) {
+    if word == 0 {
+        output.push_str(" None");
+        return;
+    }
+    let mut parts: Vec<String> = names
+        .iter()
+        .filter(|(bit, _)| word & bit != 0)
+        .map(|(_, name)| (*name).to_owned())
+        .collect();
+    let unknown = names.iter().fold(word, |rest, (bit, _)| rest & !bit);
+    if unknown != 0 {
+        parts.push(format!("{unknown:#x}"));
+    }
+    let _ = write!(output, " {}", parts.join("|"));
+}
+
+/// Nul-terminated UTF-8 string starting at `first`,
+/// returns the string and the number of the words after `first` it takes
+fn string(first: u32, rest: &[u32]) -> (String, usize) {
+    let mut bytes = Vec::new();
+    let mut length = 0;
+    for word in std::iter::once(&first).chain(rest) {
+        let chunk = word.to_le_bytes();
+        let end = chunk.iter().position(|&byte| byte == 0);
+        bytes.extend_from_slice(&chunk[..end.unwrap_or(4)]);
+        if end.is_some() {
+            break;
+        }
+        length += 1;
+    }
+    (
+        String::from_utf8_lossy(&bytes).into_owned(),
+        length.min(rest.len()),
+    )
+}
+
+fn layout(op: Op) -> Layout {
+    use Operand::*;
+    let (has_type, has_result, operands): (bool, bool, &'static [Operand]) = match op {
+        Op::Capability => (false, false, &[Capability]),
+        Op::Extension => (false, false, &[String]),
+        Op::ExtInstImport | Op::String => (false, true, &[String]),
+        Op::MemoryModel => (false, false, &[AddressingModel, MemoryModel]),
+        Op::EntryPoint => (false, false, &[ExecutionModel, Id, String, Id]),
+        Op::ExecutionMode => (false, false, &[Id, ExecutionMode, Literal]),
+        Op::Source => (false, false, &[SourceLanguage, Literal, Id, String]),
+        Op::SourceExtension | Op::ModuleProcessed => (false, false, &[String]),
+        Op::Name => (false, false, &[Id, String]),
+        Op::MemberName => (false, false, &[Id, Literal, String]),
+        Op::Line => (false, false, &[Id, Literal]),
+        Op::Decorate => (false, false, &[Id, Decoration, Literal]),
+        Op::MemberDecorate => (false, false, &[Id, Literal, Decoration, Literal]),
+        Op::TypeInt | Op::TypeFloat => (false, true, &[Literal]),
+        Op::TypeVector | Op::TypeMatrix => (false, true, &[Id, Literal]),
+        Op::TypeImage => (
+            false,
+            true,
+            &[
+                Id,
+                Dim,
+                Literal,
+                Literal,
+                Literal,
+                Literal,
+                ImageFormat,
+                Literal,
+            ],
+        ),
+        Op::TypePointer => (false, true, &[StorageClass, Id]),
+        Op::TypeForwardPointer => (false, false, &[Id, StorageClass]),
+        Op::Constant | Op::SpecConstant => (true, true, &[Literal]),
+        Op::Variable => (true, true, &[StorageClass, Id]),
+        Op::Function => (true, true, &[FunctionControl, Id]),
+        Op::Load => (true, true, &[Id, MemoryAccess, Literal]),
+        Op::Store => (false, false, &[Id, Id, MemoryAccess, Literal]),
+        Op::SelectionMerge => (false, false, &[Id, SelectionControl]),
+        Op::LoopMerge => (false, false, &[Id, Id, LoopControl]),
+        Op::Switch => (false, false, &[Id, Id, SwitchTarget]),
+        Op::CompositeExtract => (true, true, &[Id, Literal]),
+        Op::CompositeInsert => (true, true, &[Id, Id, Literal]),
+        Op::VectorShuffle => (true, true, &[Id, Id, Literal]),
+        Op::ExtInst => (true, true, &[Id, ExtInst, Id]),
+        Op::ImageWrite => (false, false, &[Id, Id, Id, ImageOperands, Id]),
+        Op::ImageSampleImplicitLod
+        | Op::ImageSampleExplicitLod
+        | Op::ImageFetch
+        | Op::ImageRead => (true, true, &[Id, Id, ImageOperands, Id]),
+        // the component or the depth reference comes before the image operands
+        Op::ImageGather
+        | Op::ImageSampleDrefImplicitLod
+        | Op::ImageSampleDrefExplicitLod
+        | Op::ImageDrefGather => (true, true, &[Id, Id, Id, ImageOperands, Id]),
+        // type declarations with ids only
+        op if format!("{op:?}").starts_with("Type") => (false, true, &[Id]),
+        Op::Label | Op::DecorationGroup => (false, true, &[]),
+        Op::Nop
+        | Op::NoLine
+        | Op::Return
+        | Op::ReturnValue
+        | Op::Branch
+        | Op::BranchConditional
+        | Op::Kill
+        | Op::TerminateInvocation
+        | Op::DemoteToHelperInvocation
+        | Op::Unreachable
+        | Op::FunctionEnd
+        | Op::ControlBarrier
+        | Op::MemoryBarrier
+        | Op::AtomicStore
+        | Op::CopyMemory
+        | Op::EmitVertex
+        | Op::EndPrimitive
+        | Op::ExecutionModeId
+        | Op::DecorateId
+        | Op::GroupDecorate
+        | Op::GroupMemberDecorate => (false, false, &[Id]),
+        _ => (true, true, &[Id]),
+    };
+    Layout {
+        has_type,
+        has_result,
+        operands,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Words of an instruction, strings are given as their words
+    fn op(op: Op, operands: &[u32]) -> Vec<u32> {
+        let mut words = vec![((operands.len() as u32 + 1) << 16) | op as u32];
+        words.extend_from_slice(operands);
+        words
+    }
+
+    fn string(text: &str) -> Vec<u32> {
+        let mut bytes = text.as_bytes().to_vec();
+        bytes.resize(text.len() / 4 * 4 + 4, 0);
+        bytes
+            .chunks_exact(4)
+            .map(|word| u32::from_le_bytes([word[0], word[1], word[2], word[3]]))
+            .collect()
+    }
+
+    fn module(instructions: &[Vec<u32>]) -> Vec<u32> {
+        let mut words = vec![spirv::MAGIC_NUMBER, 0x0001_0000, 0, 19, 0];
+        words.extend(instructions.iter().flatten());
+        words
+    }
+
+    #[test]
+    fn matches_spirv_dis() {
+        let words = module(&[
+            op(Op::Capability, &[spirv::Capability::Shader as u32]),
+            op(
+                Op::ExtInstImport,
+                &[[1].as_slice(), &string("GLSL.std.450")].concat(),
+            ),
+            op(Op::MemoryModel, &[0, 1]),
+            op(
+                Op::EntryPoint,
+                &[[4].as_slice(), &[2], &string("main"), &[3]].concat(),
+            ),
+            op(
+                Op::ExecutionMode,
+                &[2, spirv::ExecutionMode::OriginUpperLeft as u32],
+            ),
+            op(Op::Name, &[[2].as_slice(), &string("main")].concat()),
+            op(
+                Op::Decorate,
+                &[
+                    3,
+                    spirv::Decoration::BuiltIn as u32,
+                    spirv::BuiltIn::FragCoord as u32,
+                ],
+            ),
+            op(Op::TypeVoid, &[4]),
+            op(Op::TypeFunction, &[5, 4]),
+            op(Op::TypeFloat, &[6, 32]),
+            op(Op::TypeInt, &[7, 32, 1]),
+            op(Op::Constant, &[6, 8, 0.5f32.to_bits()]),
+            op(Op::Constant, &[7, 9, -1i32 as u32]),
+            op(Op::TypeVector, &[10, 6, 4]),
+            op(
+                Op::TypePointer,
+                &[11, spirv::StorageClass::Input as u32, 10],
+            ),
+            op(Op::Variable, &[11, 3, spirv::StorageClass::Input as u32]),
+            op(Op::Function, &[4, 2, 0, 5]),
+            op(Op::Label, &[12]),
+            op(Op::Load, &[10, 13, 3, 2, 16]),
+            op(Op::CompositeExtract, &[6, 14, 13, 0]),
+            op(Op::ExtInst, &[6, 15, 1, spirv::GLOp::Sin as u32, 14]),
+            op(Op::SelectionMerge, &[16, 0]),
+            op(Op::Switch, &[9, 16, 1, 17, 2, 18]),
+            op(Op::Label, &[17]),
+            op(Op::Branch, &[16]),
+            op(Op::Label, &[18]),
+            op(Op::Branch, &[16]),
+            op(Op::Label, &[16]),
+            op(Op::Return, &[]),
+            op(Op::FunctionEnd, &[]),
+        ]);
+        // `spirv-dis --raw-id`, except for the name of the generator
+        let expected = r#"; SPIR-V
+; Version: 1.0
+; Generator: 0x00000000
+; Bound: 19
+; Schema: 0
+               OpCapability Shader
+          %1 = OpExtInstImport "GLSL.std.450"
+               OpMemoryModel Logical GLSL450
+               OpEntryPoint Fragment %2 "main" %3
+               OpExecutionMode %2 OriginUpperLeft
+               OpName %2 "main"
+               OpDecorate %3 BuiltIn FragCoord
+          %4 = OpTypeVoid
+          %5 = OpTypeFunction %4
+          %6 = OpTypeFloat 32
+          %7 = OpTypeInt 32 1
+          %8 = OpConstant %6 0.5
+          %9 = OpConstant %7 -1
+         %10 = OpTypeVector %6 4
+         %11 = OpTypePointer Input %10
+          %3 = OpVariable %11 Input
+          %2 = OpFunction %4 None %5
+         %12 = OpLabel
+         %13 = OpLoad %10 %3 Aligned 16
+         %14 = OpCompositeExtract %6 %13 0
+         %15 = OpExtInst %6 %1 Sin %14
+               OpSelectionMerge %16 None
+               OpSwitch %9 %16 1 %17 2 %18
+         %17 = OpLabel
+               OpBranch %16
+         %18 = OpLabel
+               OpBranch %16
+         %16 = OpLabel
+               OpReturn
+               OpFunctionEnd
+"#;
+        assert_eq!(disassemble(&words), expected);
+    }
+
+    #[test]
+    fn string_of_whole_words() {
+        // the terminating nul takes a word of its own
+        let words = module(&[op(Op::Name, &[[2].as_slice(), &string("main")].concat())]);
+        assert!(disassemble(&words).ends_with("               OpName %2 \"main\"\n"));
+        assert_eq!(string("main").len(), 2);
+    }
+
+    #[test]
+    fn image_operands() {
+        let words = module(&[
+            op(Op::ImageSampleExplicitLod, &[1, 2, 3, 4, 0x2, 5]),
+            op(Op::ImageGather, &[1, 6, 3, 4, 7, 0x8, 8]),
+        ]);
+        let text = disassemble(&words);
+        assert!(
+            text.contains("%2 = OpImageSampleExplicitLod %1 %3 %4 Lod %5\n"),
+            "{text}"
+        );
+        assert!(
+            text.contains("%6 = OpImageGather %1 %3 %4 %7 ConstOffset %8\n"),
+            "{text}"
+        );
+    }
+
+    #[test]
+    fn invalid_modules() {
+        assert_eq!(disassemble(&[0, 1, 2]), "; not a SPIR-V module");
+        let mut words = module(&[op(Op::TypeVoid, &[1])]);
+        // the length of the last instruction goes past the end
+        words.push((3 << 16) | Op::TypeBool as u32);
+        assert!(disassemble(&words).ends_with("; truncated instruction at word 7\n"));
+    }
+}
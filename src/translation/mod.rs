@@ -0,0 +1,223 @@
+//! The shader translated by the naga backends, the same way wgpu does it for the drivers
+
+mod disassembler;
+
+use std::fmt;
+
+use naga::back::{glsl, hlsl, msl, spv};
+use naga::valid::ModuleInfo;
+use naga::{Module, ShaderStage};
+
+use crate::language::Shader;
+use crate::validator;
+
+/// Shading language of the translated shader
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Target {
+    #[default]
+    GlslEs,
+    Glsl,
+    Hlsl,
+    Msl,
+    SpirV,
+}
+
+impl Target {
+    pub const ALL: [Target; 5] = [
+        Target::GlslEs,
+        Target::Glsl,
+        Target::Hlsl,
+        Target::Msl,
+        Target::SpirV,
+    ];
+
+    /// Extension of the exported file, GLSL files are named after the stage
+    pub fn extension(self, stage: ShaderStage) -> &'static str {
+        match self {
+            Target::GlslEs | Target::Glsl => match stage {
+                ShaderStage::Vertex => "vert",
+                ShaderStage::Compute => "comp",
+                _ => "frag",
+            },
+            Target::Hlsl => "hlsl",
+            Target::Msl => "metal",
+            Target::SpirV => "spv",
+        }
+    }
+}
+
+impl fmt::Display for Target {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Target::GlslEs => "GLSL ES 3.10",
+            Target::Glsl => "GLSL 4.50",
+            Target::Hlsl => "HLSL 5.1",
+            Target::Msl => "MSL 2.0",
+            Target::SpirV => "SPIR-V 1.0",
+        };
+        write!(f, "{name}")
+    }
+}
+
+/// Output of a backend for a single entry point
+pub struct Translation {
+    /// Source code, or the disassembly for SPIR-V
+    pub text: String,
+    /// Content of the exported file, the binary module for SPIR-V
+    pub file: Vec<u8>,
+}
+
+/// Translates the entry point of the shader with the built-in declarations
+pub fn translate(
+    shader: &Shader,
+    target: Target,
+    entry_point: &str,
+) -> Result<Translation, String> {
+    let (module, info, _) = validator::parse(shader).map_err(|diagnostic| diagnostic.message)?;
+    translate_module(&module, &info, target, entry_point)
+}
+
+/// Translates a module validated before, like the one of the background validation
+pub fn translate_module(
+    module: &Module,
+    info: &ModuleInfo,
+    target: Target,
+    entry_point: &str,
+) -> Result<Translation, String> {
+    let stage = module
+        .entry_points
+        .iter()
+        .find(|entry| entry.name == entry_point)
+        .map(|entry| entry.stage)
+        .ok_or_else(|| format!("The shader has no entry point {entry_point}"))?;
+
+    let text = match target {
+        Target::GlslEs => write_glsl(
+            module,
+            info,
+            glsl::Version::new_gles(310),
+            stage,
+            entry_point,
+        ),
+        Target::Glsl => write_glsl(
+            module,
+            info,
+            glsl::Version::Desktop(450),
+            stage,
+            entry_point,
+        ),
+        Target::Hlsl => write_hlsl(module, info, stage, entry_point),
+        Target::Msl => write_msl(module, info, stage, entry_point),
+        Target::SpirV => {
+            let words = write_spirv(module, info, stage, entry_point)?;
+            return Ok(Translation {
+                text: disassembler::disassemble(&words),
+                file: words.iter().flat_map(|word| word.to_le_bytes()).collect(),
+            });
+        }
+    }?;
+    Ok(Translation {
+        file: text.clone().into_bytes(),
+        text,
+    })
+}
+
+/// Disassembly of a binary SPIR-V module
+pub fn disassemble(bytes: &[u8]) -> String {
+    if !bytes.len().is_multiple_of(4) {
+        return "; not a SPIR-V module, the size is not a multiple of 4 bytes".to_owned();
+    }
+    let words: Vec<u32> = bytes
+        .chunks_exact(4)
+        .map(|word| u32::from_le_bytes([word[0], word[1], word[2], word[3]]))
+        .collect();
+    disassembler::disassemble(&words)
+}
+
+fn write_glsl(
+    module: &Module,
+    info: &ModuleInfo,
+    version: glsl::Version,
+    stage: ShaderStage,
+    entry_point: &str,
+) -> Result<String, String> {
+    let options = glsl::Options {
+        version,
+        ..Default::default()
+    };
+    let pipeline_options = glsl::PipelineOptions {
+        shader_stage: stage,
+        entry_point: entry_point.to_owned(),
+        multiview: None,
+    };
+    let mut output = String::new();
+    glsl::Writer::new(
+        &mut output,
+        module,
+        info,
+        &options,
+        &pipeline_options,
+        naga::proc::BoundsCheckPolicies::default(),
+    )
+    .and_then(|mut writer| writer.write())
+    .map_err(|e| format!("GLSL backend error: {e}"))?;
+    Ok(output)
+}
+
+fn write_hlsl(
+    module: &Module,
+    info: &ModuleInfo,
+    stage: ShaderStage,
+    entry_point: &str,
+) -> Result<String, String> {
+    let options = hlsl::Options::default();
+    let pipeline_options = hlsl::PipelineOptions {
+        entry_point: Some((stage, entry_point.to_owned())),
+    };
+    let mut output = String::new();
+    hlsl::Writer::new(&mut output, &options, &pipeline_options)
+        .write(module, info, None)
+        .map_err(|e| format!("HLSL backend error: {e}"))?;
+    Ok(output)
+}
+
+fn write_msl(
+    module: &Module,
+    info: &ModuleInfo,
+    stage: ShaderStage,
+    entry_point: &str,
+) -> Result<String, String> {
+    let options = msl::Options {
+        lang_version: (2, 0),
+        ..Default::default()
+    };
+    let pipeline_options = msl::PipelineOptions {
+        entry_point: Some((stage, entry_point.to_owned())),
+        ..Default::default()
+    };
+    msl::write_string(module, info, &options, &pipeline_options)
+        .map(|(output, _)| output)
+        .map_err(|e| format!("MSL backend error: {e}"))
+}
+
+fn write_spirv(
+    module: &Module,
+    info: &ModuleInfo,
+    stage: ShaderStage,
+    entry_point: &str,
+) -> Result<Vec<u32>, String> {
+    // the flags of wgpu, with the debug names to make the disassembly readable
+    let options = spv::Options {
+        flags: spv::WriterFlags::DEBUG
+            | spv::WriterFlags::ADJUST_COORDINATE_SPACE
+            | spv::WriterFlags::LABEL_VARYINGS
+            | spv::WriterFlags::CLAMP_FRAG_DEPTH,
+        ..Default::default()
+    };
+    let pipeline_options = spv::PipelineOptions {
+        shader_stage: stage,
+        entry_point: entry_point.to_owned(),
+    };
+    spv::write_vec(module, info, &options, Some(&pipeline_options))
+        .map_err(|e| format!("SPIR-V backend error: {e}"))
+}
@@ -1,10 +1,181 @@
-use naga::valid::{Capabilities, ValidationFlags, Validator};
+use std::collections::HashMap;
+use std::sync::{Arc, mpsc};
+use std::time::Duration;
 
-pub fn validate(shader: &str) -> Result<(), String> {
-    let parsed =
-        naga::front::wgsl::parse_str(shader).map_err(|parse_error| parse_error.to_string())?;
-    let _ = Validator::new(ValidationFlags::default(), Capabilities::all())
+use iced_winit::winit::event_loop::EventLoopProxy;
+use naga::valid::{Capabilities, ModuleInfo, ValidationFlags, Validator};
+
+use crate::diagnostic::Diagnostic;
+use crate::entry_points::EntryPoints;
+use crate::language::Shader;
+use crate::params::{self, Param};
+use crate::prelude::Composed;
+use crate::{CustomEvent, prelude};
+
+/// A shader which passed validation, reused to render it without parsing it again
+#[derive(Debug)]
+pub struct Validation {
+    pub module: naga::Module,
+    pub info: ModuleInfo,
+    pub composed: Composed,
+    pub entry_points: EntryPoints,
+    pub params: Vec<Param>,
+}
+
+pub fn validate(shader: &Shader) -> Result<Validation, Diagnostic> {
+    let (module, info, composed) = parse(shader)?;
+    Ok(Validation {
+        entry_points: EntryPoints::from_module(&module),
+        params: params::reflect(&module, shader.source()).map_err(Diagnostic::error)?,
+        module,
+        info,
+        composed,
+    })
+}
+
+/// Parses and validates the shader with the built-in declarations
+/// by the front end of its language
+pub fn parse(shader: &Shader) -> Result<(naga::Module, ModuleInfo, Composed), Diagnostic> {
+    let (parsed, text, composed) = match shader {
+        Shader::Wgsl(text) => {
+            let (parsed, composed) = parse_wgsl(text)?;
+            (parsed, text.as_str(), composed)
+        }
+        Shader::Glsl(stage, text) => {
+            let composed = prelude::compose_glsl(text, *stage);
+            let options = naga::front::glsl::Options::from(*stage);
+            let parsed = naga::front::glsl::Frontend::default()
+                .parse(&options, &composed.source)
+                .map_err(|errors| Diagnostic::from_glsl_errors(&errors, text, &composed))?;
+            let parsed = apply_default_sampling(parsed);
+            (parsed, text.as_str(), composed)
+        }
+        Shader::SpirV(bytes) => {
+            let options = naga::front::spv::Options::default();
+            let parsed = naga::front::spv::parse_u8_slice(bytes, &options)
+                .map_err(|e| Diagnostic::error(format!("Invalid SPIR-V module: {e}")))?;
+            let parsed = apply_default_sampling(parsed);
+            let composed = prelude::compose_spirv(&parsed);
+            (parsed, "", composed)
+        }
+    };
+    let info = Validator::new(ValidationFlags::default(), Capabilities::all())
         .validate(&parsed)
-        .map_err(|e| e.to_string())?;
-    Ok(())
+        .map_err(|e| Diagnostic::from_validation_error(&e, text, &composed))?;
+    Ok((parsed, info, composed))
+}
+
+/// Fragment-only shaders use the `FragmentInput` of the built-in vertex stage,
+/// so the shader is parsed with it first. It's left out when the parsed module
+/// has a vertex entry point of its own, or when the shader only parses without it.
+fn parse_wgsl(text: &str) -> Result<(naga::Module, Composed), Diagnostic> {
+    let composed = prelude::compose(text, true);
+    let fragment_only_error = match naga::front::wgsl::parse_str(&composed.source) {
+        Ok(module) => {
+            let vertex_count = module
+                .entry_points
+                .iter()
+                .filter(|entry_point| entry_point.stage == naga::ShaderStage::Vertex)
+                .count();
+            // the built-in stage is the only one
+            if vertex_count == 1 {
+                return Ok((module, composed));
+            }
+            None
+        }
+        Err(parse_error) => {
+            // an error pointing into the built-in stage is a conflict with it
+            let is_in_shader = parse_error
+                .labels()
+                .all(|(span, _)| composed.user_span(span).is_some());
+            is_in_shader.then(|| Diagnostic::from_parse_error(&parse_error, text, &composed))
+        }
+    };
+
+    let composed = prelude::compose(text, false);
+    match naga::front::wgsl::parse_str(&composed.source) {
+        Ok(module) => Ok((module, composed)),
+        Err(parse_error) => Err(fragment_only_error
+            .unwrap_or_else(|| Diagnostic::from_parse_error(&parse_error, text, &composed))),
+    }
+}
+
+/// The front ends leave the sampling of the inputs unset, while the
+/// built-in full-screen triangle is written in WGSL with the defaults
+fn apply_default_sampling(mut module: naga::Module) -> naga::Module {
+    for entry_point in &mut module.entry_points {
+        for argument in &mut entry_point.function.arguments {
+            if let Some(naga::Binding::Location {
+                interpolation: Some(naga::Interpolation::Perspective),
+                sampling: sampling @ None,
+                ..
+            }) = &mut argument.binding
+            {
+                *sampling = Some(naga::Sampling::Center);
+            }
+        }
+    }
+    module
+}
+
+/// Time without new requests before the validation starts
+const DEBOUNCE: Duration = Duration::from_millis(300);
+
+struct Request {
+    document: u64,
+    revision: u64,
+    shader: Shader,
+}
+
+/// Validates shaders on a background thread while the user is typing,
+/// sends [`CustomEvent::Validated`] with the result
+pub struct BackgroundValidator {
+    sender: mpsc::Sender<Request>,
+}
+
+impl BackgroundValidator {
+    pub fn new(event_loop_proxy: EventLoopProxy<CustomEvent>) -> Self {
+        let (sender, receiver) = mpsc::channel::<Request>();
+
+        let _handle = std::thread::spawn(move || {
+            while let Ok(request) = receiver.recv() {
+                // wait for the edits to settle, only the latest text of every document
+                // is validated
+                let mut pending = HashMap::from([(request.document, request)]);
+                loop {
+                    match receiver.recv_timeout(DEBOUNCE) {
+                        Ok(newer) => {
+                            pending.insert(newer.document, newer);
+                        }
+                        Err(mpsc::RecvTimeoutError::Timeout) => break,
+                        Err(mpsc::RecvTimeoutError::Disconnected) => return,
+                    }
+                }
+
+                for request in pending.into_values() {
+                    let event = CustomEvent::Validated {
+                        document: request.document,
+                        revision: request.revision,
+                        result: validate(&request.shader).map(Arc::new),
+                    };
+                    if event_loop_proxy.send_event(event).is_err() {
+                        return;
+                    }
+                }
+            }
+        });
+
+        BackgroundValidator { sender }
+    }
+
+    pub fn request(&self, document: u64, revision: u64, shader: Shader) {
+        let request = Request {
+            document,
+            revision,
+            shader,
+        };
+        if let Err(e) = self.sender.send(request) {
+            println!("Error sending validation request: {e}");
+        }
+    }
 }
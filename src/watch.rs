@@ -1,31 +1,93 @@
-use std::sync::mpsc;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, mpsc};
 
 use iced_winit::winit::event_loop::EventLoopProxy;
 use notify::{Config, RecommendedWatcher, RecursiveMode, Watcher};
 
-use super::{CustomEvent, SHADER_FILE};
+use super::CustomEvent;
 
-pub fn init(event_loop_proxy: EventLoopProxy<CustomEvent>) {
-    let _handle = std::thread::spawn(move || {
-        let (tx, rx) = mpsc::channel();
+/// Watches the currently opened shader file,
+/// sends [`CustomEvent::ShaderFileChanged`] on changes
+pub struct FileWatcher {
+    watcher: RecommendedWatcher,
+    /// Directory being watched, editors saving via rename-and-replace
+    /// make the watch on the file itself go stale
+    directory: Option<PathBuf>,
+    /// Shared with the event thread to filter out other files
+    path: Arc<Mutex<Option<PathBuf>>>,
+}
 
-        let mut watcher = RecommendedWatcher::new(tx, Config::default()).unwrap();
+impl FileWatcher {
+    /// Stops watching the previous file and starts watching `path`
+    pub fn watch(&mut self, path: &Path) -> Result<(), String> {
+        self.unwatch();
+
+        let directory = match path.parent() {
+            Some(parent) if !parent.as_os_str().is_empty() => parent.to_path_buf(),
+            _ => PathBuf::from("."),
+        };
+        self.watcher
+            .watch(&directory, RecursiveMode::NonRecursive)
+            .map_err(|e| format!("Error watching {}: {e}", path.display()))?;
+        self.directory = Some(directory);
+        if let Ok(mut watched) = self.path.lock() {
+            *watched = Some(path.to_path_buf());
+        }
+        Ok(())
+    }
 
-        watcher
-            .watch(SHADER_FILE.as_ref(), RecursiveMode::Recursive)
-            .unwrap();
+    pub fn unwatch(&mut self) {
+        if let Some(previous) = self.directory.take() {
+            let _ = self.watcher.unwatch(&previous);
+        }
+        if let Ok(mut watched) = self.path.lock() {
+            *watched = None;
+        }
+    }
+}
 
+fn is_watched(watched: &Mutex<Option<PathBuf>>, paths: &[PathBuf]) -> bool {
+    let Ok(watched) = watched.lock() else {
+        return false;
+    };
+    let Some(file_name) = watched.as_deref().and_then(Path::file_name) else {
+        return false;
+    };
+    // only a single directory is watched, the file name is enough
+    paths.iter().any(|path| path.file_name() == Some(file_name))
+}
+
+pub fn init(event_loop_proxy: EventLoopProxy<CustomEvent>) -> Result<FileWatcher, String> {
+    let (tx, rx) = mpsc::channel();
+
+    let watcher = RecommendedWatcher::new(tx, Config::default())
+        .map_err(|e| format!("Error creating the file watcher: {e}"))?;
+    let watched = Arc::new(Mutex::new(None));
+
+    let thread_watched = watched.clone();
+    let _handle = std::thread::spawn(move || {
         for res in rx {
             match res {
-                Ok(_event) => {
-                    let shader_text =
-                        std::fs::read_to_string(SHADER_FILE).expect("Should read the shader");
-                    event_loop_proxy
-                        .send_event(CustomEvent::UpdateShader(shader_text))
-                        .expect("Should send custom winit event");
+                // reading the file is reported as access, skip it
+                Ok(event) if event.kind.is_access() => (),
+                Ok(event) if is_watched(&thread_watched, &event.paths) => {
+                    if event_loop_proxy
+                        .send_event(CustomEvent::ShaderFileChanged)
+                        .is_err()
+                    {
+                        // the event loop is closed
+                        return;
+                    }
                 }
+                Ok(_event) => (),
                 Err(e) => println!("watch error: {e:?}"),
             }
         }
     });
+
+    Ok(FileWatcher {
+        watcher,
+        directory: None,
+        path: watched,
+    })
 }
@@ -0,0 +1,39 @@
+use std::process::{Command, Output};
+
+fn golden(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_shader-editor"))
+        .arg("golden")
+        .args(args)
+        .current_dir(env!("CARGO_MANIFEST_DIR"))
+        .output()
+        .expect("Should run shader-editor")
+}
+
+fn assert_success(output: &Output) {
+    assert!(
+        output.status.success(),
+        "{}{}",
+        String::from_utf8_lossy(&output.stdout),
+        String::from_utf8_lossy(&output.stderr)
+    );
+}
+
+/// The interpreter gives the same images on every machine, unlike the GPU
+#[test]
+fn golden_cpu() {
+    assert_success(&golden(&["--cpu"]));
+}
+
+/// Renders with the headless device and the pipelines of the editor,
+/// skipped on machines without any adapter, not even a software one
+#[test]
+fn golden_gpu() {
+    let output = golden(&[]);
+    if !output.status.success()
+        && String::from_utf8_lossy(&output.stderr).starts_with("Adapter not found")
+    {
+        println!("Skipped, no adapter");
+        return;
+    }
+    assert_success(&output);
+}